@@ -1,20 +1,17 @@
 use criterion::{black_box, Criterion};
 use reqwest::blocking::Client;
-use std::env;
-use std::fmt::Display;
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    io::{self},
+    path::{Path, PathBuf},
+};
 
 pub fn fetch_input(day: u32) -> String {
-    let session_cookie = env::var("AOC_SESSION").expect("AOC_SESSION environment variable not set");
-    let client = Client::new();
-    let url = format!("https://adventofcode.com/2024/day/{}/input", day);
-
-    client
-        .get(&url)
-        .header("Cookie", format!("session={}", session_cookie))
-        .send()
-        .expect("Failed to send request")
-        .text()
-        .expect("Failed to read response text")
+    InputProvider::from_env()
+        .input(day)
+        .unwrap_or_else(|e| panic!("no input for day {}: {}", day, e))
 }
 
 pub fn run_benchmarks<D1, D2>(c: &mut Criterion, day: u32, part1: D1, part2: D2)
@@ -31,4 +28,134 @@ where
     c.bench_function(&format!("day{}_part2", day), |b| {
         b.iter(|| part2(black_box(&input)))
     });
-}
\ No newline at end of file
+}
+
+const YEAR: u32 = 2024;
+const AOC_URL: &str = "https://adventofcode.com";
+
+pub trait Fetcher {
+    fn fetch(&self, day: u32) -> Result<String, Error>;
+}
+
+/// Fetches puzzle input over HTTP using an adventofcode.com session cookie.
+pub struct HttpFetcher {
+    base_url: String,
+    session: String,
+    client: Client,
+}
+
+impl HttpFetcher {
+    pub fn new(base_url: &str, session: &str) -> Self {
+        HttpFetcher {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session: session.to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Reads the session from `AOC_SESSION`; `AOC_URL` overrides the server.
+    pub fn from_env() -> Result<Self, Error> {
+        let session = env::var("AOC_SESSION").map_err(|_| Error::MissingSession)?;
+        let base_url = env::var("AOC_URL").unwrap_or_else(|_| AOC_URL.to_string());
+        Ok(HttpFetcher::new(&base_url, &session))
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, day: u32) -> Result<String, Error> {
+        let url = format!("{}/{}/day/{}/input", self.base_url, YEAR, day);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Cookie", format!("session={}", self.session))
+            .send()?
+            .error_for_status()?;
+        Ok(response.text()?)
+    }
+}
+
+/// Looks up puzzle input in `<cache_dir>/2024/dayN.txt` and only asks the
+/// fetcher for days that are missing, storing what it got for next time.
+pub struct InputProvider {
+    cache_dir: PathBuf,
+    fetcher: Option<Box<dyn Fetcher>>,
+}
+
+impl InputProvider {
+    pub fn new(cache_dir: impl AsRef<Path>, fetcher: Option<Box<dyn Fetcher>>) -> Self {
+        InputProvider {
+            cache_dir: cache_dir.as_ref().to_path_buf(),
+            fetcher,
+        }
+    }
+
+    /// Cache in `input/`, falling back to [`HttpFetcher::from_env`] if a
+    /// session is configured.
+    pub fn from_env() -> Self {
+        let fetcher = HttpFetcher::from_env()
+            .ok()
+            .map(|f| Box::new(f) as Box<dyn Fetcher>);
+        InputProvider::new("input", fetcher)
+    }
+
+    pub fn cache_path(&self, day: u32) -> PathBuf {
+        self.cache_dir
+            .join(YEAR.to_string())
+            .join(format!("day{}.txt", day))
+    }
+
+    pub fn input(&self, day: u32) -> Result<String, Error> {
+        let path = self.cache_path(day);
+        match fs::read_to_string(&path) {
+            Ok(input) => return Ok(input),
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            Err(_) => {}
+        }
+
+        let fetcher = self
+            .fetcher
+            .as_ref()
+            .ok_or(Error::NotCached(path.clone()))?;
+        let input = fetcher.fetch(day)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, &input)?;
+        Ok(input)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    IO(io::Error),
+    Http(reqwest::Error),
+    MissingSession,
+    NotCached(PathBuf),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::IO(e) => write!(f, "{}", e),
+            Error::Http(e) => write!(f, "{}", e),
+            Error::MissingSession => write!(f, "AOC_SESSION environment variable not set"),
+            Error::NotCached(path) => {
+                write!(f, "{} not cached and no fetcher configured", path.display())
+            }
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::IO(value)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        Error::Http(value)
+    }
+}
@@ -1,4 +1,5 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use aoc2024::{day4::WordSearch, day5::CustomOrd};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
 mod common;
 
 macro_rules! generate_day_benchmarks {
@@ -13,8 +14,8 @@ macro_rules! generate_day_benchmarks {
                         common::run_benchmarks(
                             c,
                             $day,
-                            |input| Box::new(part1(input)),
-                            |input| Box::new(part2(input)),
+                            |input| Box::new(part1(input).unwrap()),
+                            |input| Box::new(part2(input).unwrap()),
                         );
                     }
                 }
@@ -31,5 +32,62 @@ macro_rules! generate_day_benchmarks {
 
 generate_day_benchmarks!(1, 2, 3, 4, 5, 6); // Add new days here
 
-criterion_group!(benches, register_benchmarks);
+/// Naive and batch day 4 word search for a dictionary of the letter runs of
+/// the first rows.
+fn word_search(c: &mut Criterion) {
+    let input = common::fetch_input(4);
+    let puzzle = aoc2024::day4::parse_input(&input).unwrap();
+    let mut words: Vec<&str> = input
+        .lines()
+        .take(2)
+        .flat_map(|line| {
+            (3..=6).flat_map(move |len| {
+                (0..=line.len().saturating_sub(len)).map(move |i| &line[i..i + len])
+            })
+        })
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    let search = WordSearch::new(&words);
+
+    c.bench_function("day4_find", |b| b.iter(|| puzzle.find(black_box(&search))));
+    c.bench_function("day4_find_batch", |b| {
+        b.iter(|| puzzle.find_batch(black_box(&search)))
+    });
+}
+
+/// Day 5 order checks with the rules in a bit matrix and in hash maps, and
+/// the check of every pair of pages.
+fn rule_lookup(c: &mut Criterion) {
+    let (rules, updates) = aoc2024::day5::parse_input(&common::fetch_input(5)).unwrap();
+    let dense = CustomOrd::new(rules.clone());
+    let hashed = CustomOrd::hashed(rules);
+
+    c.bench_function("day5_is_ordered_dense", |b| {
+        b.iter(|| {
+            updates
+                .iter()
+                .filter(|u| dense.is_ordered(black_box(u)))
+                .count()
+        })
+    });
+    c.bench_function("day5_is_ordered_hashed", |b| {
+        b.iter(|| {
+            updates
+                .iter()
+                .filter(|u| hashed.is_ordered(black_box(u)))
+                .count()
+        })
+    });
+    c.bench_function("day5_check_pairs_hashed", |b| {
+        b.iter(|| {
+            updates
+                .iter()
+                .filter(|u| hashed.check(black_box(u)).is_empty())
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, register_benchmarks, word_search, rule_lookup);
 criterion_main!(benches);
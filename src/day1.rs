@@ -1,58 +1,203 @@
 use std::{
-    collections::{BinaryHeap, HashMap},
-    io::{self},
+    cmp::{self, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    env,
+    fmt::Debug,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    iter,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    vec,
+};
+
+use crate::{
+    error::{Error, ParseErrorKind},
+    number::{Arithmetic, Number},
 };
 
 #[aoc(day1, part1)]
-pub fn part1(input: &str) -> i32 {
+pub fn part1(input: &str) -> Result<i128, Error> {
     let mut a = BinaryHeap::<i32>::new();
     let mut b = BinaryHeap::<i32>::new();
-    read_from(input, &mut a, &mut b).unwrap();
+    read_from(input, ParseMode::Strict, &mut a, &mut b)?;
 
     let a: Vec<i32> = a.into_sorted_vec();
     let b: Vec<i32> = b.into_sorted_vec();
-    distance_sorted(&a, &b)
+    distance_sorted(&a, &b, Arithmetic::Checked)
+        .map(i128::from)
+        .or_else(|| distance_sorted(&widen(&a), &widen(&b), Arithmetic::Checked))
+        .ok_or(Error::Overflow("distance".to_string()))
 }
 
 #[aoc(day1, part2)]
-pub fn part2(input: &str) -> i32 {
+pub fn part2(input: &str) -> Result<i128, Error> {
     let mut a = BinaryHeap::<i32>::new();
     let mut b = BinaryHeap::<i32>::new();
-    read_from(input, &mut a, &mut b).unwrap();
+    read_from(input, ParseMode::Strict, &mut a, &mut b)?;
 
     let a: Vec<i32> = a.into_sorted_vec();
     let b: Vec<i32> = b.into_sorted_vec();
-    similarity_score(&a, &b)
-}
-
-fn read_from(input: &str, a: &mut BinaryHeap<i32>, b: &mut BinaryHeap<i32>) -> Result<(), Error> {
-    for line in input.lines() {
-        if let Ok(cells) = line
-            .split_whitespace()
-            .map(|s| s.parse::<i32>())
-            .collect::<Result<Vec<i32>, _>>()
-        {
-            if cells.len() != 2 {
-                return Err(Error::InputTwoColumsExpected(cells.len()));
-            } else {
-                a.push(cells[0]);
-                b.push(cells[1]);
+    similarity_score(&a, &b, Arithmetic::Checked)
+        .map(i128::from)
+        .or_else(|| similarity_score(&widen(&a), &widen(&b), Arithmetic::Checked))
+        .ok_or(Error::Overflow("similarity score".to_string()))
+}
+
+#[aoc(day1, part1, rows)]
+pub fn part1_rows(input: &str) -> Result<String, Error> {
+    let (a, b) = sorted_columns(input)?;
+    let diffs = row_diffs(&widen(&a), &widen(&b), Arithmetic::Checked)
+        .ok_or(Error::Overflow("distance".to_string()))?;
+    Ok(diffs
+        .iter()
+        .map(|d| format!("\n{}   {}   {}", d.left, d.right, d.distance))
+        .collect())
+}
+
+#[aoc(day1, part2, jaccard)]
+pub fn part2_jaccard(input: &str) -> Result<f64, Error> {
+    let (a, b) = sorted_columns(input)?;
+    Ok(jaccard(&a, &b))
+}
+
+#[aoc(day1, part2, intersection)]
+pub fn part2_intersection(input: &str) -> Result<usize, Error> {
+    let (a, b) = sorted_columns(input)?;
+    Ok(multiset_intersection(&a, &b).len())
+}
+
+#[aoc(day1, part2, difference)]
+pub fn part2_difference(input: &str) -> Result<usize, Error> {
+    let (a, b) = sorted_columns(input)?;
+    Ok(multiset_difference(&a, &b).len())
+}
+
+#[aoc(day1, part2, spearman)]
+pub fn part2_spearman(input: &str) -> Result<f64, Error> {
+    let (rows, _) = read_rows(input, ParseMode::Strict)?;
+    let (a, b): (Vec<i32>, Vec<i32>) = rows.into_iter().unzip();
+    Ok(spearman(&a, &b).unwrap_or(f64::NAN))
+}
+
+#[aoc(day1, part2, kendall)]
+pub fn part2_kendall(input: &str) -> Result<f64, Error> {
+    let (rows, _) = read_rows(input, ParseMode::Strict)?;
+    let (a, b): (Vec<i32>, Vec<i32>) = rows.into_iter().unzip();
+    Ok(kendall(&a, &b).unwrap_or(f64::NAN))
+}
+
+fn sorted_columns(input: &str) -> Result<(Vec<i32>, Vec<i32>), Error> {
+    let mut a = BinaryHeap::<i32>::new();
+    let mut b = BinaryHeap::<i32>::new();
+    read_from(input, ParseMode::Strict, &mut a, &mut b)?;
+    Ok((a.into_sorted_vec(), b.into_sorted_vec()))
+}
+
+fn widen(ids: &[i32]) -> Vec<i128> {
+    ids.iter().map(|id| i128::from(*id)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseMode {
+    /// Fail on the first malformed line.
+    Strict,
+    /// Skip malformed lines and report them as warnings.
+    Lenient,
+}
+
+/// Reads the two location id columns. Blank lines are ignored, `\r\n` line
+/// endings and signed values are accepted. Returns the warnings collected in
+/// [`ParseMode::Lenient`].
+pub fn read_from(
+    input: &str,
+    mode: ParseMode,
+    a: &mut BinaryHeap<i32>,
+    b: &mut BinaryHeap<i32>,
+) -> Result<Vec<Error>, Error> {
+    let (rows, warnings) = read_rows(input, mode)?;
+    for (left, right) in rows {
+        a.push(left);
+        b.push(right);
+    }
+    Ok(warnings)
+}
+
+pub type Row = (i32, i32);
+
+/// Like [`read_from`] but keeps the rows paired in input order.
+pub fn read_rows(input: &str, mode: ParseMode) -> Result<(Vec<Row>, Vec<Error>), Error> {
+    let mut rows = Vec::new();
+    let mut warnings = Vec::new();
+    for (line_idx, line) in input.lines().enumerate() {
+        match parse_line(line) {
+            Ok(None) => {}
+            Ok(Some(row)) => rows.push(row),
+            Err(e) => {
+                let e = e.on_line(line_idx + 1);
+                match mode {
+                    ParseMode::Strict => return Err(e),
+                    ParseMode::Lenient => warnings.push(e),
+                }
             }
         }
     }
-    Ok(())
+    Ok((rows, warnings))
 }
 
-pub fn similarity_score(a: &[i32], b: &[i32]) -> i32 {
+fn parse_line(line: &str) -> Result<Option<(i32, i32)>, Error> {
+    let mut cells = Vec::with_capacity(2);
+    for token in line.split_whitespace() {
+        let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+        let column = line[..offset].chars().count() + 1;
+        if cells.len() == 2 {
+            return Err(Error::parsing(
+                1,
+                column,
+                ParseErrorKind::ColumnsExpected {
+                    expected: 2,
+                    found: line.split_whitespace().count(),
+                },
+            ));
+        }
+        let value = token.parse::<i32>().map_err(|e| {
+            Error::parsing(
+                1,
+                column,
+                ParseErrorKind::Int {
+                    token: token.to_string(),
+                    source: e,
+                },
+            )
+        })?;
+        cells.push(value);
+    }
+    match cells[..] {
+        [] => Ok(None),
+        [left, right] => Ok(Some((left, right))),
+        _ => Err(Error::parsing(
+            1,
+            line.trim_end().chars().count() + 1,
+            ParseErrorKind::ColumnsExpected {
+                expected: 2,
+                found: cells.len(),
+            },
+        )),
+    }
+}
+
+pub fn similarity_score<T: Number>(a: &[T], b: &[T], arithmetic: Arithmetic) -> Option<T> {
     let frequency = frequency(a);
 
-    b.iter().fold(0, |score, entry| {
-        score + *(frequency.get(entry).get_or_insert(&0)) * entry
+    b.iter().try_fold(T::ZERO, |score, entry| {
+        let count = arithmetic.count(*frequency.get(entry).unwrap_or(&0))?;
+        arithmetic.add(score, arithmetic.mul(count, *entry)?)
     })
 }
 
-pub fn frequency(a: &[i32]) -> HashMap<i32, i32> {
-    let frequency = a.iter().fold(HashMap::<i32, i32>::new(), |mut map, entry| {
+pub fn frequency<T: Number>(a: &[T]) -> HashMap<T, usize> {
+    let frequency = a.iter().fold(HashMap::<T, usize>::new(), |mut map, entry| {
         if let Some(v) = map.get_mut(entry) {
             *v += 1;
         } else {
@@ -63,33 +208,405 @@ pub fn frequency(a: &[i32]) -> HashMap<i32, i32> {
     frequency
 }
 
-#[derive(Debug)]
-pub enum Error {
-    InputTwoColumsExpected(usize),
-    InputFileIOError(io::Error),
+pub fn distance_sorted<T: Number>(a: &[T], b: &[T], arithmetic: Arithmetic) -> Option<T> {
+    a.iter().zip(b.iter()).try_fold(T::ZERO, |distance, p| {
+        arithmetic.add(distance, arithmetic.abs_diff(*p.0, *p.1)?)
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RowDiff<T> {
+    pub left: T,
+    pub right: T,
+    pub distance: T,
 }
 
-pub fn distance_sorted(a: &[i32], b: &[i32]) -> i32 {
-    a.iter().zip(b.iter()).map(|p| (p.0 - p.1).abs()).sum()
+/// The pairs [`distance_sorted`] adds up, one per row.
+pub fn row_diffs<T: Number>(a: &[T], b: &[T], arithmetic: Arithmetic) -> Option<Vec<RowDiff<T>>> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(left, right)| {
+            Some(RowDiff {
+                left: *left,
+                right: *right,
+                distance: arithmetic.abs_diff(*left, *right)?,
+            })
+        })
+        .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use std::fs::File;
+/// Share of distinct ids appearing in both lists, 1.0 for two empty lists.
+pub fn jaccard<T: Number>(a: &[T], b: &[T]) -> f64 {
+    let a: HashSet<&T> = a.iter().collect();
+    let b: HashSet<&T> = b.iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Ids contained in both lists, each as often as in the list having fewer of
+/// them, in ascending order.
+pub fn multiset_intersection<T: Number>(a: &[T], b: &[T]) -> Vec<T> {
+    let frequency_b = frequency(b);
+    multiset(frequency(a), |id, count| {
+        count.min(*frequency_b.get(id).unwrap_or(&0))
+    })
+}
+
+/// Ids of `a` left over after removing one occurrence per id in `b`, in
+/// ascending order.
+pub fn multiset_difference<T: Number>(a: &[T], b: &[T]) -> Vec<T> {
+    let frequency_b = frequency(b);
+    multiset(frequency(a), |id, count| {
+        count.saturating_sub(*frequency_b.get(id).unwrap_or(&0))
+    })
+}
+
+fn multiset<T: Number>(frequency: HashMap<T, usize>, count: impl Fn(&T, usize) -> usize) -> Vec<T> {
+    let mut ids: Vec<T> = frequency
+        .iter()
+        .flat_map(|(id, c)| iter::repeat_n(*id, count(id, *c)))
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Spearman's rank correlation of the rows `(a[i], b[i])`, ties get their
+/// average rank. `None` if either column is constant or there are less than
+/// two rows.
+pub fn spearman<T: Number>(a: &[T], b: &[T]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
+    let rank_a = ranks(&a[..n]);
+    let rank_b = ranks(&b[..n]);
+    let mean = (n as f64 + 1.0) / 2.0;
+
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (ra, rb) in rank_a.iter().zip(rank_b.iter()) {
+        cov += (ra - mean) * (rb - mean);
+        var_a += (ra - mean) * (ra - mean);
+        var_b += (rb - mean) * (rb - mean);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return None;
+    }
+    Some(cov / (var_a * var_b).sqrt())
+}
+
+fn ranks<T: Number>(values: &[T]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by_key(|idx| values[*idx]);
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end) as f64 / 2.0 + 1.0;
+        for idx in &order[start..=end] {
+            ranks[*idx] = rank;
+        }
+        start = end + 1;
+    }
+    ranks
+}
+
+/// Kendall's tau-b of the rows `(a[i], b[i])`. `None` if either column is
+/// constant or there are less than two rows.
+pub fn kendall<T: Number>(a: &[T], b: &[T]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    let (mut concordant, mut discordant) = (0i64, 0i64);
+    let (mut ties_a, mut ties_b) = (0i64, 0i64);
+    for i in 0..n {
+        for j in i + 1..n {
+            match (a[i].cmp(&a[j]), b[i].cmp(&b[j])) {
+                (cmp::Ordering::Equal, cmp::Ordering::Equal) => {
+                    ties_a += 1;
+                    ties_b += 1;
+                }
+                (cmp::Ordering::Equal, _) => ties_a += 1,
+                (_, cmp::Ordering::Equal) => ties_b += 1,
+                (o1, o2) if o1 == o2 => concordant += 1,
+                _ => discordant += 1,
+            }
+        }
+    }
+    let pairs = (n * n.saturating_sub(1) / 2) as i64;
+    let denominator = (((pairs - ties_a) * (pairs - ties_b)) as f64).sqrt();
+    if denominator == 0.0 {
+        return None;
+    }
+    Some((concordant - discordant) as f64 / denominator)
+}
+
+/// Streaming counterpart of [`similarity_score`], only the id frequencies of
+/// both columns are kept in memory.
+pub fn similarity_score_streaming<R: Read>(input: R) -> Result<i128, Error> {
+    let mut left = HashMap::<i32, usize>::new();
+    let mut right = HashMap::<i32, usize>::new();
+    for_each_row(input, |a, b| {
+        *left.entry(a).or_default() += 1;
+        *right.entry(b).or_default() += 1;
+        Ok(())
+    })?;
+
+    left.iter()
+        .try_fold(0i128, |score, (id, count)| {
+            let occurrences = count.checked_mul(*right.get(id).unwrap_or(&0))?;
+            score.checked_add(i128::from(*id).checked_mul(i128::try_from(occurrences).ok()?)?)
+        })
+        .ok_or(Error::Overflow("similarity score".to_string()))
+}
+
+/// Streaming counterpart of [`distance_sorted`]. Both columns are sorted in
+/// runs of at most `run_len` values which are spilled to temporary files and
+/// merged afterwards.
+pub fn distance_sorted_streaming<R: Read>(input: R, run_len: usize) -> Result<i128, Error> {
+    let run_len = run_len.max(1);
+    let mut left = ExternalSort::new(run_len);
+    let mut right = ExternalSort::new(run_len);
+    for_each_row(input, |a, b| {
+        left.push(a)?;
+        right.push(b)
+    })?;
+
+    let mut left = left.finish()?;
+    let mut right = right.finish()?;
+    let mut distance = 0i128;
+    while let (Some(a), Some(b)) = (left.next(), right.next()) {
+        distance = distance
+            .checked_add(i128::from(a?).abs_diff(i128::from(b?)) as i128)
+            .ok_or(Error::Overflow("distance".to_string()))?;
+    }
+    Ok(distance)
+}
+
+fn for_each_row<R, F>(input: R, mut f: F) -> Result<(), Error>
+where
+    R: Read,
+    F: FnMut(i32, i32) -> Result<(), Error>,
+{
+    for (line_idx, line) in BufReader::new(input).lines().enumerate() {
+        if let Some((a, b)) = parse_line(&line?).map_err(|e| e.on_line(line_idx + 1))? {
+            f(a, b)?;
+        }
+    }
+    Ok(())
+}
+
+/// Most run files [`ExternalSort`] merges at once, more are merged into
+/// intermediate runs first so the number of open files stays bounded.
+const MAX_FAN_IN: usize = 64;
+
+struct ExternalSort {
+    run_len: usize,
+    fan_in: usize,
+    buffer: Vec<i32>,
+    runs: Vec<Run>,
+}
 
-    use io::Read;
+impl ExternalSort {
+    fn new(run_len: usize) -> Self {
+        ExternalSort::with_fan_in(run_len, MAX_FAN_IN)
+    }
+
+    fn with_fan_in(run_len: usize, fan_in: usize) -> Self {
+        ExternalSort {
+            run_len,
+            fan_in: fan_in.max(2),
+            // grows as needed, `run_len` may be far beyond the input size
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, value: i32) -> Result<(), Error> {
+        self.buffer.push(value);
+        if self.buffer.len() >= self.run_len {
+            self.buffer.sort_unstable();
+            self.runs
+                .push(Run::write(self.buffer.iter().map(|value| Ok(*value)))?);
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Merge, Error> {
+        // the oldest runs are merged first, so every value is rewritten
+        // about log_fan_in(runs) times
+        while self.runs.len() > self.fan_in {
+            let batch: Vec<Run> = self.runs.drain(..self.fan_in).collect();
+            let sources = batch
+                .into_iter()
+                .map(|run| run.open().map(RunSource::File))
+                .collect::<Result<Vec<_>, Error>>()?;
+            self.runs.push(Run::write(Merge::new(sources)?)?);
+        }
+
+        self.buffer.sort_unstable();
+        let mut sources = vec![RunSource::Memory(self.buffer.into_iter())];
+        for run in self.runs {
+            sources.push(RunSource::File(run.open()?));
+        }
+        Merge::new(sources)
+    }
+}
+
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A sorted run of values in a temporary file, removed when dropped.
+struct Run {
+    path: PathBuf,
+}
+
+impl Run {
+    fn write<I: IntoIterator<Item = Result<i32, Error>>>(values: I) -> Result<Run, Error> {
+        let id = RUN_ID.fetch_add(1, Ordering::Relaxed);
+        let run = Run {
+            path: env::temp_dir().join(format!("aoc2024-day1-{}-{}.run", process::id(), id)),
+        };
+        let mut writer = BufWriter::new(File::create(&run.path)?);
+        for value in values {
+            writer.write_all(&value?.to_le_bytes())?;
+        }
+        writer.flush()?;
+        Ok(run)
+    }
+
+    fn open(self) -> Result<RunReader, Error> {
+        let reader = BufReader::new(File::open(&self.path)?);
+        Ok(RunReader { reader, _run: self })
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+struct RunReader {
+    reader: BufReader<File>,
+    _run: Run,
+}
 
+enum RunSource {
+    Memory(vec::IntoIter<i32>),
+    File(RunReader),
+}
+
+impl RunSource {
+    fn next(&mut self) -> Result<Option<i32>, Error> {
+        match self {
+            RunSource::Memory(values) => Ok(values.next()),
+            RunSource::File(run) => {
+                let mut bytes = [0; 4];
+                match run.reader.read_exact(&mut bytes) {
+                    Ok(()) => Ok(Some(i32::from_le_bytes(bytes))),
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            }
+        }
+    }
+}
+
+/// K-way merge of sorted runs, yielding all values in ascending order.
+struct Merge {
+    sources: Vec<RunSource>,
+    heads: BinaryHeap<Reverse<(i32, usize)>>,
+}
+
+impl Merge {
+    fn new(mut sources: Vec<RunSource>) -> Result<Self, Error> {
+        let mut heads = BinaryHeap::new();
+        for (idx, source) in sources.iter_mut().enumerate() {
+            if let Some(value) = source.next()? {
+                heads.push(Reverse((value, idx)));
+            }
+        }
+        Ok(Merge { sources, heads })
+    }
+}
+
+impl Iterator for Merge {
+    type Item = Result<i32, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((value, idx)) = self.heads.pop()?;
+        match self.sources[idx].next() {
+            Ok(Some(next)) => self.heads.push(Reverse((next, idx))),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
 
     #[test]
     fn read_into_simple() {
         let mut a = BinaryHeap::<i32>::new();
         let mut b = BinaryHeap::<i32>::new();
-        read_into("test_data/day1.txt", &mut a, &mut b).unwrap();
+        let warnings = read_into("test_data/day1.txt", &mut a, &mut b).unwrap();
+        assert!(warnings.is_empty());
         assert_eq!(a.into_sorted_vec(), vec![1, 2, 3, 3, 3, 4]);
         assert_eq!(b.into_sorted_vec(), vec![3, 3, 3, 4, 5, 9]);
     }
 
+    #[test]
+    fn read_from_wrong_column_count() {
+        let mut a = BinaryHeap::<i32>::new();
+        let mut b = BinaryHeap::<i32>::new();
+
+        let e = read_from("3   4\n1 2 3\n", ParseMode::Strict, &mut a, &mut b).unwrap_err();
+        assert_eq!(
+            "line 2, column 5: expected 2 columns, found 3",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn read_from_strict_invalid_token() {
+        let mut a = BinaryHeap::<i32>::new();
+        let mut b = BinaryHeap::<i32>::new();
+
+        let e = read_from("3   4\n1   x2\n", ParseMode::Strict, &mut a, &mut b).unwrap_err();
+        assert_eq!(
+            "line 2, column 5: invalid number \"x2\" (invalid digit found in string)",
+            e.to_string()
+        );
+        assert_eq!(Err(e), part1("3   4\n1   x2\n"),);
+    }
+
+    #[test]
+    fn read_from_lenient_collects_warnings() {
+        let mut a = BinaryHeap::<i32>::new();
+        let mut b = BinaryHeap::<i32>::new();
+
+        let input = "3   4\r\n\r\n-1   x\r\n7\r\n-2   +5\r\n";
+        let warnings = read_from(input, ParseMode::Lenient, &mut a, &mut b).unwrap();
+
+        assert_eq!(a.into_sorted_vec(), vec![-2, 3]);
+        assert_eq!(b.into_sorted_vec(), vec![4, 5]);
+        assert_eq!(
+            warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            vec![
+                "line 3, column 6: invalid number \"x\" (invalid digit found in string)",
+                "line 4, column 2: expected 2 columns, found 1",
+            ]
+        );
+    }
+
     #[test]
     fn frequency_simple() {
         let a = vec![3, 4, 2, 1, 3, 3];
@@ -105,22 +622,178 @@ mod tests {
         let a = vec![3, 4, 2, 1, 3, 3];
         let b = vec![4, 3, 5, 3, 9, 3];
 
-        assert_eq!(31, similarity_score(&a, &b));
+        assert_eq!(Some(31), similarity_score(&a, &b, Arithmetic::Checked));
+    }
+
+    #[test]
+    fn similarity_score_overflow() {
+        let a = vec![2_000_000_000, 2_000_000_000];
+        let b = vec![2_000_000_000];
+
+        assert_eq!(None, similarity_score(&a, &b, Arithmetic::Checked));
+        assert_eq!(
+            Some(i32::MAX),
+            similarity_score(&a, &b, Arithmetic::Saturating)
+        );
+        assert_eq!(
+            Some(i32::MIN),
+            similarity_score(&[-a[0], -a[1]], &[-b[0]], Arithmetic::Saturating)
+        );
+        assert_eq!(
+            Some(4_000_000_000u64),
+            similarity_score(
+                &[2_000_000_000u64; 2],
+                &[2_000_000_000u64],
+                Arithmetic::Checked
+            )
+        );
+    }
+
+    #[test]
+    fn list_metrics_test_data() {
+        let (a, b) = sorted_columns(&fs::read_to_string("test_data/day1.txt").unwrap()).unwrap();
+
+        assert!((jaccard(&a, &b) - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(vec![3, 3, 3, 4], multiset_intersection(&a, &b));
+        assert_eq!(vec![1, 2], multiset_difference(&a, &b));
+        assert_eq!(vec![5, 9], multiset_difference(&b, &a));
+        assert_eq!(
+            Some(RowDiff {
+                left: 1,
+                right: 3,
+                distance: 2
+            }),
+            row_diffs(&a, &b, Arithmetic::Checked)
+                .unwrap()
+                .into_iter()
+                .next()
+        );
+    }
+
+    #[test]
+    fn rank_correlation_test_data() {
+        let input = fs::read_to_string("test_data/day1.txt").unwrap();
+
+        let spearman = part2_spearman(&input).unwrap();
+        let kendall = part2_kendall(&input).unwrap();
+        assert!((spearman + 3.0 / 31.0).abs() < 1e-12);
+        assert!((kendall + 1.0 / 12.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rank_correlation_extremes() {
+        let a = [1, 5, 7, 9];
+        let b = [10, 20, 30, 31];
+        let reversed = [4, 3, 2, 1];
+
+        assert_eq!(Some(1.0), spearman(&a, &b));
+        assert_eq!(Some(-1.0), spearman(&a, &reversed));
+        assert_eq!(Some(1.0), kendall(&a, &b));
+        assert_eq!(Some(-1.0), kendall(&a, &reversed));
+        assert_eq!(None, spearman(&a, &[2, 2, 2, 2]));
+        assert_eq!(None, kendall(&[1], &[1]));
+    }
+
+    #[test]
+    fn distance_sorted_overflow() {
+        assert_eq!(
+            None,
+            distance_sorted(&[i32::MIN], &[i32::MAX], Arithmetic::Checked)
+        );
+        assert_eq!(
+            Some(i32::MAX),
+            distance_sorted(&[i32::MIN, 0], &[i32::MAX, 5], Arithmetic::Saturating)
+        );
+        assert_eq!(
+            Some(u64::MAX),
+            distance_sorted(&[0, u64::MAX], &[u64::MAX, 0], Arithmetic::Saturating)
+        );
+        assert_eq!(
+            Some(u32::MAX as i64),
+            distance_sorted(&[i32::MIN as i64], &[i32::MAX as i64], Arithmetic::Checked)
+        );
+    }
+
+    #[test]
+    fn parts_widen_on_overflow() {
+        let input = "2000000000   2000000000\n-2000000000   2000000000\n";
+
+        assert_eq!(Ok(4_000_000_000), part1(input));
+        assert_eq!(Ok(4_000_000_000), part2(input));
+        assert_eq!(part1(input), distance_sorted_streaming(input.as_bytes(), 1));
+        assert_eq!(part2(input), similarity_score_streaming(input.as_bytes()));
+    }
+
+    #[test]
+    fn streaming_matches_in_memory_test_data() {
+        let input = fs::read_to_string("test_data/day1.txt").unwrap();
+
+        assert_eq!(
+            part1(&input),
+            distance_sorted_streaming(input.as_bytes(), 2)
+        );
+        assert_eq!(part2(&input), similarity_score_streaming(input.as_bytes()));
+    }
+
+    #[test]
+    fn streaming_matches_in_memory_generated() {
+        let mut seed: u32 = 0x2024;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % 100
+        };
+        let input: String = (0..1000)
+            .map(|_| format!("{}   {}\n", next(), next()))
+            .collect();
+
+        for run_len in [1, 7, 1000, 5000, usize::MAX] {
+            assert_eq!(
+                part1(&input),
+                distance_sorted_streaming(input.as_bytes(), run_len)
+            );
+        }
+        assert_eq!(part2(&input), similarity_score_streaming(input.as_bytes()));
+    }
+
+    #[test]
+    fn external_sort_bounds_open_runs() {
+        let mut sort = ExternalSort::with_fan_in(1, 4);
+        let values: Vec<i32> = (0..100).map(|v| (v * 37) % 101 - 50).collect();
+        for value in &values {
+            sort.push(*value).unwrap();
+        }
+        assert_eq!(100, sort.runs.len());
+
+        let merge = sort.finish().unwrap();
+        // the in-memory remainder and at most `fan_in` run files
+        assert!(merge.sources.len() <= 5);
+
+        let mut expected = values;
+        expected.sort_unstable();
+        assert_eq!(Ok(expected), merge.collect::<Result<Vec<i32>, Error>>());
+    }
+
+    #[test]
+    fn streaming_reports_line() {
+        let e = similarity_score_streaming("1   2\n3\n".as_bytes()).unwrap_err();
+
+        assert_eq!(
+            "line 2, column 2: expected 2 columns, found 1",
+            e.to_string()
+        );
     }
 
     fn read_into(
         path: &str,
         a: &mut BinaryHeap<i32>,
         b: &mut BinaryHeap<i32>,
-    ) -> Result<(), Error> {
-        let input = File::open(path)
-            .and_then(|mut f| {
-                let mut buf = String::new();
-                f.read_to_string(&mut buf).map(|_| buf)
-            })
-            .map_err(Error::InputFileIOError)?;
+    ) -> Result<Vec<Error>, Error> {
+        let input = File::open(path).and_then(|mut f| {
+            let mut buf = String::new();
+            f.read_to_string(&mut buf).map(|_| buf)
+        })?;
 
         let input = input.as_str();
-        read_from(input, a, b)
+        read_from(input, ParseMode::Strict, a, b)
     }
 }
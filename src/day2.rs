@@ -1,73 +1,249 @@
-use std::{io, num::ParseIntError};
+use std::{
+    fmt::{self, Display},
+    ops::RangeInclusive,
+};
+
+use crate::error::{Error, ParseErrorKind};
 
 #[aoc(day2, part1)]
-pub fn part1(input: &str) -> usize {
-    let input = from(input);
-    evaluate_report(&input).unwrap()
+pub fn part1(input: &str) -> Result<usize, Error> {
+    let input = from(input)?;
+    evaluate_report(&input, &SafetyRules::default())
 }
 
 #[aoc(day2, part2)]
-pub fn part2(input: &str) -> usize {
-    let input = from(input);
-    evaluate_reports_with_dumping(&input).unwrap()
+pub fn part2(input: &str) -> Result<usize, Error> {
+    let input = from(input)?;
+    evaluate_reports_with_dumping(&input, &SafetyRules::default())
+}
+
+#[aoc(day2, part2, explain)]
+pub fn part2_explain(input: &str) -> Result<String, Error> {
+    Ok(format!(
+        "\n{}",
+        render_diagnoses(input, &SafetyRules::default())?
+    ))
 }
 
-fn evaluate_reports_with_dumping(input: &[Vec<i32>]) -> Result<usize, Error> {
+pub fn evaluate_reports_with_dumping(
+    input: &[Vec<i32>],
+    rules: &SafetyRules,
+) -> Result<usize, Error> {
     let safe_reports = input
         .iter()
-        .filter(|r| {
-            if !is_safe_with_dumping(r) {
-                false
-            } else {
-                true
-            }
-        })
+        .filter(|r| is_safe_with_dumping(r, rules))
         .count();
     Ok(safe_reports)
 }
 
-fn evaluate_report(input: &[Vec<i32>]) -> Result<usize, Error> {
-    let safe_reports = input.iter().filter(|r| is_safe(r).is_ok()).count();
+pub fn evaluate_report(input: &[Vec<i32>], rules: &SafetyRules) -> Result<usize, Error> {
+    let safe_reports = input.iter().filter(|r| is_safe(r, rules).is_ok()).count();
     Ok(safe_reports)
 }
 
-fn from(input: &str) -> Vec<Vec<i32>> {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Either,
+    Increasing,
+    Decreasing,
+}
+
+/// What makes a report safe. The default is the puzzle's rule: strictly
+/// increasing or decreasing by 1 to 3 per step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafetyRules {
+    /// Allowed absolute difference between adjacent levels.
+    pub step: RangeInclusive<u32>,
+    /// Adjacent equal levels are accepted regardless of `step`.
+    pub allow_equal: bool,
+    pub direction: Direction,
+    /// Largest allowed absolute difference of any level to the first one.
+    pub max_drift: Option<u32>,
+}
+
+impl Default for SafetyRules {
+    fn default() -> Self {
+        SafetyRules {
+            step: 1..=3,
+            allow_equal: false,
+            direction: Direction::Either,
+            max_drift: None,
+        }
+    }
+}
+
+impl SafetyRules {
+    fn step_ok(&self, step: u64) -> bool {
+        (step == 0 && self.allow_equal)
+            || u32::try_from(step).is_ok_and(|step| self.step.contains(&step))
+    }
+
+    fn drift_ok(&self, first: i32, level: i32) -> bool {
+        self.max_drift
+            .is_none_or(|max| first.abs_diff(level) <= max)
+    }
+}
+
+fn from(input: &str) -> Result<Vec<Vec<i32>>, Error> {
     let mut input_parsed = Vec::<Vec<i32>>::new();
-    for l in input.lines() {
-        let r: Vec<i32> = l
-            .split_ascii_whitespace()
-            .map(|s| s.parse::<i32>())
-            .flat_map(Result::ok)
-            .collect();
+    for (line_idx, l) in input.lines().enumerate() {
+        let r = parse_line(l).map_err(|e| e.on_line(line_idx + 1))?;
         input_parsed.push(r);
     }
-    input_parsed
+    Ok(input_parsed)
+}
+
+fn parse_line(line: &str) -> Result<Vec<i32>, Error> {
+    line.split_ascii_whitespace()
+        .map(|token| {
+            token.parse::<i32>().map_err(|e| {
+                let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+                Error::parsing(
+                    1,
+                    line[..offset].chars().count() + 1,
+                    ParseErrorKind::Int {
+                        token: token.to_string(),
+                        source: e,
+                    },
+                )
+            })
+        })
+        .collect()
 }
 
 #[derive(Debug, PartialEq)]
-enum ErrorAt {
+pub enum ErrorAt {
     Pos(usize),
 }
 
-fn is_safe_with_dumping(report: &[i32]) -> bool {
-    match is_safe(report) {
+pub fn is_safe_with_dumping(report: &[i32], rules: &SafetyRules) -> bool {
+    match is_safe(report, rules) {
         Ok(()) => true,
-        Err(ErrorAt::Pos(1..=2)) => {
-            is_safe(&dump_pos(report, 0)).is_ok() || is_safe(&dump_pos(report, 1)).is_ok()
-        }
-        Err(ErrorAt::Pos(pos)) => {
-            let report_altered = dump_pos(report, pos);
-            is_safe(&report_altered).is_ok()
+        Err(ErrorAt::Pos(pos)) => dumping_fix(report, rules, pos).is_some(),
+    }
+}
+
+/// The level the dampener removes to make a report failing at `pos` safe.
+///
+/// Removing a level before `pos - 2` leaves the failing pair and the one
+/// before it untouched, so it can only help if it changes the direction set
+/// by the first unequal pair, or the first level a drift is measured from.
+fn dumping_fix(report: &[i32], rules: &SafetyRules, pos: usize) -> Option<usize> {
+    let direction_set_at = (1..report.len())
+        .find(|idx| report[*idx] != report[idx - 1])
+        .unwrap_or(1);
+    let mut candidates = vec![
+        direction_set_at - 1,
+        direction_set_at,
+        pos.saturating_sub(2),
+        pos.saturating_sub(1),
+        pos,
+    ];
+    if rules.max_drift.is_some() {
+        candidates.push(0);
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .filter(|idx| *idx < report.len())
+        .find(|idx| is_safe(&dump_pos(report, *idx), rules).is_ok())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    DirectionChange,
+    WrongDirection,
+    StepZero,
+    StepTooSmall,
+    StepTooLarge,
+    Drift,
+}
+
+/// The first pair of levels breaking the rules, `from` and `to` are indices
+/// into the report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub reason: Reason,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    pub report: Vec<i32>,
+    /// `None` if the report is safe as it is.
+    pub violation: Option<Violation>,
+    /// Index of the level whose removal made an unsafe report safe.
+    pub fixed_by: Option<usize>,
+}
+
+/// Explains the verdict [`is_safe`] and [`is_safe_with_dumping`] reach for
+/// `report`.
+pub fn diagnose(report: &[i32], rules: &SafetyRules) -> Diagnosis {
+    let violation = find_violation(report, rules);
+    let fixed_by = violation
+        .as_ref()
+        .and_then(|v| dumping_fix(report, rules, v.to));
+    Diagnosis {
+        report: report.to_vec(),
+        violation,
+        fixed_by,
+    }
+}
+
+/// Annotates every report line of `input` with its [`Diagnosis`].
+pub fn render_diagnoses(input: &str, rules: &SafetyRules) -> Result<String, Error> {
+    let width = input.lines().map(|l| l.len()).max().unwrap_or(0);
+    Ok(input
+        .lines()
+        .zip(from(input)?)
+        .map(|(line, report)| format!("{:width$}  {}\n", line, diagnose(&report, rules)))
+        .collect())
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Reason::DirectionChange => "direction change",
+            Reason::WrongDirection => "wrong direction",
+            Reason::StepZero => "step zero",
+            Reason::StepTooSmall => "step too small",
+            Reason::StepTooLarge => "step too large",
+            Reason::Drift => "drift too large",
+        };
+        f.write_str(reason)
+    }
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(v) = &self.violation else {
+            return write!(f, "safe");
+        };
+        match self.fixed_by {
+            Some(idx) => write!(f, "safe without level {} ({}), ", idx + 1, self.report[idx])?,
+            None => write!(f, "unsafe, ")?,
         }
+        write!(
+            f,
+            "{} from {} to {} at levels {}-{}",
+            v.reason,
+            self.report[v.from],
+            self.report[v.to],
+            v.from + 1,
+            v.to + 1
+        )
     }
 }
 
-fn is_safe_dumped_brute(report: &[i32]) -> bool {
-    match is_safe(report) {
+#[cfg(test)]
+fn is_safe_dumped_brute(report: &[i32], rules: &SafetyRules) -> bool {
+    match is_safe(report, rules) {
         Ok(()) => true,
         Err(ErrorAt::Pos(..)) => {
             for i in 0..report.len() {
-                if is_safe(&dump_pos(report, i)).is_ok() {
+                if is_safe(&dump_pos(report, i), rules).is_ok() {
                     return true;
                 }
             }
@@ -76,6 +252,88 @@ fn is_safe_dumped_brute(report: &[i32]) -> bool {
     }
 }
 
+/// Generalized problem dampener: the smallest set of level indices whose
+/// removal makes `report` safe under `rules`, or `None` if that takes more
+/// than `tolerance` removals. Runs in `O(n * tolerance)`, times `tolerance`
+/// again if `rules` limit the drift.
+pub fn min_removals(report: &[i32], rules: &SafetyRules, tolerance: usize) -> Option<Vec<usize>> {
+    if report.is_empty() {
+        return Some(vec![]);
+    }
+    let directions: &[bool] = match rules.direction {
+        Direction::Either => &[true, false],
+        Direction::Increasing => &[true],
+        Direction::Decreasing => &[false],
+    };
+    // the drift is measured from the first level kept
+    let firsts: Vec<Option<usize>> = match rules.max_drift {
+        Some(_) => (0..report.len().min(tolerance + 1)).map(Some).collect(),
+        None => vec![None],
+    };
+    directions
+        .iter()
+        .flat_map(|increasing| firsts.iter().map(move |first| (*increasing, *first)))
+        .filter_map(|(increasing, first)| {
+            min_removals_directed(report, rules, tolerance, increasing, first)
+        })
+        .min_by_key(|removed| removed.len())
+}
+
+/// [`min_removals`] for reports going in one direction and, if given,
+/// keeping `first` as the first level.
+fn min_removals_directed(
+    report: &[i32],
+    rules: &SafetyRules,
+    tolerance: usize,
+    increasing: bool,
+    first: Option<usize>,
+) -> Option<Vec<usize>> {
+    let n = report.len();
+    let step_ok = |before: i32, after: i32| {
+        rules.step_ok(u64::from(before.abs_diff(after)))
+            && (before == after || (before < after) == increasing)
+    };
+    let level_ok = |i: usize| first.is_none_or(|first| rules.drift_ok(report[first], report[i]));
+
+    // removed[i]: fewest removals among report[..=i] keeping level i,
+    // previous[i]: the level kept right before i in that solution
+    let mut removed: Vec<Option<usize>> = vec![None; n];
+    let mut previous: Vec<Option<usize>> = vec![None; n];
+    for i in 0..n {
+        if !level_ok(i) || first.is_some_and(|first| i < first) {
+            continue;
+        }
+        if i <= tolerance && first.is_none_or(|first| i == first) {
+            removed[i] = Some(i);
+        }
+        for j in i.saturating_sub(tolerance + 1)..i {
+            if let Some(before) = removed[j] {
+                let candidate = before + (i - j - 1);
+                if candidate <= tolerance
+                    && step_ok(report[j], report[i])
+                    && removed[i].is_none_or(|r| candidate < r)
+                {
+                    removed[i] = Some(candidate);
+                    previous[i] = Some(j);
+                }
+            }
+        }
+    }
+
+    let (last, _) = (n.saturating_sub(tolerance + 1)..n)
+        .filter_map(|i| removed[i].map(|r| (i, r + (n - 1 - i))))
+        .filter(|(_, total)| *total <= tolerance)
+        .min_by_key(|(_, total)| *total)?;
+
+    let mut kept = vec![false; n];
+    let mut current = Some(last);
+    while let Some(i) = current {
+        kept[i] = true;
+        current = previous[i];
+    }
+    Some((0..n).filter(|i| !kept[*i]).collect())
+}
+
 fn dump_pos(report: &[i32], pos: usize) -> Vec<i32> {
     let mut report_altered = report.to_owned();
 
@@ -83,125 +341,448 @@ fn dump_pos(report: &[i32], pos: usize) -> Vec<i32> {
     report_altered
 }
 
-fn is_safe(report: &[i32]) -> Result<(), ErrorAt> {
+pub fn is_safe(report: &[i32], rules: &SafetyRules) -> Result<(), ErrorAt> {
+    match find_violation(report, rules) {
+        Some(v) => Err(ErrorAt::Pos(v.to)),
+        None => Ok(()),
+    }
+}
+
+fn find_violation(report: &[i32], rules: &SafetyRules) -> Option<Violation> {
+    let violation = |reason, from, to| Some(Violation { reason, from, to });
     let mut before: Option<i32> = None;
     let mut increasing: Option<bool> = None;
     for (idx, level) in report.iter().enumerate() {
         (increasing, before) = match (
             increasing,
-            before.map(|b| b < *level),
-            before.map(|b| (b - *level).abs()),
+            before.filter(|b| b != level).map(|b| b < *level),
+            before.map(|b| b.abs_diff(*level)),
+            rules.direction,
         ) {
-            (.., Some(local_increase)) if !(1..=3).contains(&local_increase) => {
+            (.., Some(local_increase), _) if !rules.step_ok(u64::from(local_increase)) => {
                 // increase amount critical
-                return Err(ErrorAt::Pos(idx));
+                let reason = match local_increase {
+                    0 => Reason::StepZero,
+                    step if step < *rules.step.start() => Reason::StepTooSmall,
+                    _ => Reason::StepTooLarge,
+                };
+                return violation(reason, idx - 1, idx);
+            }
+            _ if !rules.drift_ok(report[0], *level) => {
+                return violation(Reason::Drift, 0, idx);
+            }
+            (increasing, None, ..) => (increasing, Some(*level)),
+            (None, Some(true), _, Direction::Decreasing)
+            | (None, Some(false), _, Direction::Increasing) => {
+                return violation(Reason::WrongDirection, idx - 1, idx);
             }
-            (None, None, ..) => (None, Some(*level)),
             (None, Some(local_increasing), ..) => (Some(local_increasing), Some(*level)),
             (Some(true), Some(true), ..) => (Some(true), Some(*level)),
             (Some(false), Some(false), ..) => (Some(false), Some(*level)),
             _ => {
-                return Err(ErrorAt::Pos(idx));
+                return violation(Reason::DirectionChange, idx - 1, idx);
             }
         }
     }
-    Ok(())
-}
-
-#[derive(Debug)]
-pub enum Error {
-    InputError(io::Error),
-    ParsingInput(ParseIntError),
-}
-
-impl From<io::Error> for Error {
-    fn from(value: io::Error) -> Self {
-        Error::InputError(value)
-    }
-}
-
-impl From<ParseIntError> for Error {
-    fn from(value: ParseIntError) -> Self {
-        Error::ParsingInput(value)
-    }
+    None
 }
 
 #[cfg(test)]
 mod tests {
 
-    use std::fs::File;
-
-    use io::Read;
+    use std::{fs::File, io::Read};
 
     use super::*;
+    use crate::test_rng::Rng;
 
     #[test]
     fn is_safe_simple_false() {
         let a = vec![3, 4, 2, 1, 3, 3];
-        assert_eq!(Err(ErrorAt::Pos(2)), is_safe(&a));
+        assert_eq!(Err(ErrorAt::Pos(2)), is_safe(&a, &SafetyRules::default()));
     }
 
     #[test]
     fn is_safe_true_simple() {
         let a = vec![1, 2, 3, 5, 7, 8];
-        assert_eq!(Ok(()), is_safe(&a));
+        assert_eq!(Ok(()), is_safe(&a, &SafetyRules::default()));
     }
     #[test]
     fn is_safe_critical_increase() {
         let a = vec![1, 2, 7, 8, 9];
-        assert_eq!(Err(ErrorAt::Pos(2)), is_safe(&a));
+        assert_eq!(Err(ErrorAt::Pos(2)), is_safe(&a, &SafetyRules::default()));
     }
 
     #[test]
-    fn is_safe_dumped_false() {
-        assert_eq!(false, is_safe_with_dumping(&vec![9, 7, 6, 2, 1]));
-        assert_eq!(false, is_safe_with_dumping(&vec![1, 2, 7, 8, 9]));
+    fn is_safe_custom_rules() {
+        let rules = SafetyRules {
+            step: 1..=5,
+            allow_equal: true,
+            direction: Direction::Increasing,
+            max_drift: Some(8),
+        };
+
+        assert_eq!(Ok(()), is_safe(&[1, 1, 6, 7, 7, 9], &rules));
+        assert_eq!(Err(ErrorAt::Pos(2)), is_safe(&[1, 1, 7], &rules));
+        assert_eq!(Err(ErrorAt::Pos(1)), is_safe(&[5, 4, 3], &rules));
+        assert_eq!(Err(ErrorAt::Pos(3)), is_safe(&[1, 5, 9, 10], &rules));
+        assert_eq!(
+            Err(ErrorAt::Pos(1)),
+            is_safe(
+                &[9, 6],
+                &SafetyRules {
+                    direction: Direction::Increasing,
+                    ..SafetyRules::default()
+                }
+            )
+        );
     }
 
     #[test]
-    fn is_safe_dumped_true() {
-        assert_eq!(true, is_safe_with_dumping(&vec![7, 6, 4, 2, 1]));
-        assert_eq!(true, is_safe_with_dumping(&vec![1, 3, 2, 4, 5]));
-        assert_eq!(true, is_safe_with_dumping(&vec![8, 6, 4, 4, 1]));
-        assert_eq!(true, is_safe_with_dumping(&vec![1, 3, 6, 7, 9]));
-        assert_eq!(true, is_safe_with_dumping(&vec![6, 7, 5, 4, 3]));
+    fn is_safe_with_dumping_custom_rules() {
+        let rules = SafetyRules {
+            max_drift: Some(5),
+            ..SafetyRules::default()
+        };
+
+        assert!(is_safe_with_dumping(&[1, 4, 6, 7, 9], &rules));
+        assert!(!is_safe_dumped_brute(&[1, 4, 6, 7, 9, 10], &rules));
+        assert!(!is_safe_with_dumping(&[1, 4, 6, 7, 9, 10], &rules));
+        assert_eq!(
+            0,
+            evaluate_report(&[vec![1, 2, 3, 4, 5, 6, 7]], &rules).unwrap()
+        );
+        assert_eq!(
+            0,
+            evaluate_reports_with_dumping(&[vec![1, 2, 3, 4, 5, 6, 7, 8]], &rules).unwrap()
+        );
     }
 
     #[test]
-    fn is_safe_dumped_debug_true() {
+    fn diagnose_reasons() {
+        let rules = SafetyRules::default();
+
         assert_eq!(
-            true,
-            is_safe_with_dumping(&vec![84, 82, 83, 84, 85, 88, 90])
+            Some(Violation {
+                reason: Reason::DirectionChange,
+                from: 1,
+                to: 2
+            }),
+            diagnose(&[1, 3, 2, 4, 5], &rules).violation
         );
+        assert_eq!(Some(1), diagnose(&[1, 3, 2, 4, 5], &rules).fixed_by);
         assert_eq!(
-            true,
-            is_safe_with_dumping(&vec![70, 79, 82, 85, 86, 88, 89, 90])
+            Some(Reason::StepZero),
+            diagnose(&[8, 6, 4, 4, 1], &rules)
+                .violation
+                .map(|v| v.reason)
         );
+        assert_eq!(None, diagnose(&[1, 2, 7, 8, 9], &rules).fixed_by);
+        assert_eq!(None, diagnose(&[7, 6, 4, 2, 1], &rules).violation);
+    }
+
+    #[test]
+    fn render_diagnoses_test_data() {
+        let input = load("test_data/day2.txt").unwrap();
+
+        assert_eq!(
+            "7 6 4 2 1  safe
+1 2 7 8 9  unsafe, step too large from 2 to 7 at levels 2-3
+9 7 6 2 1  unsafe, step too large from 6 to 2 at levels 3-4
+1 3 2 4 5  safe without level 2 (3), direction change from 3 to 2 at levels 2-3
+8 6 4 4 1  safe without level 3 (4), step zero from 4 to 4 at levels 3-4
+1 3 6 7 9  safe
+",
+            render_diagnoses(&input, &SafetyRules::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn diagnoses_agree_with_evaluate() {
+        let input = parse_input("test_data/day2.txt").unwrap();
+        let rules = SafetyRules::default();
+        let fixed = input
+            .iter()
+            .map(|r| diagnose(r, &rules))
+            .filter(|d| d.violation.is_none() || d.fixed_by.is_some())
+            .count();
+
         assert_eq!(
-            true,
-            is_safe_with_dumping(&vec![79, 90, 82, 85, 86, 88, 89, 90])
+            evaluate_reports_with_dumping(&input, &rules).unwrap(),
+            fixed
         );
     }
+
+    #[test]
+    fn is_safe_dumped_false() {
+        assert!(!is_safe_with_dumping(
+            &[9, 7, 6, 2, 1],
+            &SafetyRules::default()
+        ));
+        assert!(!is_safe_with_dumping(
+            &[1, 2, 7, 8, 9],
+            &SafetyRules::default()
+        ));
+    }
+
+    #[test]
+    fn is_safe_dumped_true() {
+        assert!(is_safe_with_dumping(
+            &[7, 6, 4, 2, 1],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(
+            &[1, 3, 2, 4, 5],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(
+            &[8, 6, 4, 4, 1],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(
+            &[1, 3, 6, 7, 9],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(
+            &[6, 7, 5, 4, 3],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(&[1, 0, 5], &SafetyRules::default()));
+    }
+
+    #[test]
+    fn is_safe_dumped_debug_true() {
+        assert!(is_safe_with_dumping(
+            &[84, 82, 83, 84, 85, 88, 90],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(
+            &[70, 79, 82, 85, 86, 88, 89, 90],
+            &SafetyRules::default()
+        ));
+        assert!(is_safe_with_dumping(
+            &[79, 90, 82, 85, 86, 88, 89, 90],
+            &SafetyRules::default()
+        ));
+    }
+    #[test]
+    fn min_removals_simple() {
+        assert_eq!(Some(vec![]), min_removals_default(&[7, 6, 4, 2, 1], 0));
+        assert_eq!(None, min_removals_default(&[9, 7, 6, 2, 1], 1));
+        assert_eq!(Some(vec![3, 4]), min_removals_default(&[9, 7, 6, 2, 1], 2));
+        assert_eq!(Some(vec![3]), min_removals_default(&[8, 6, 4, 4, 1], 1));
+        assert_eq!(
+            Some(vec![0]),
+            min_removals_default(&[84, 82, 83, 84, 85, 88, 90], 3)
+        );
+        assert_eq!(Some(vec![]), min_removals_default(&[], 0));
+    }
+
+    #[test]
+    fn min_removals_is_minimal() {
+        let report = [1, 10, 2, 20, 3, 30, 4];
+        let removed = min_removals_default(&report, 5).unwrap();
+
+        assert_eq!(vec![1, 3, 5], removed);
+        assert_eq!(None, min_removals_default(&report, 2));
+    }
+
+    #[test]
+    fn min_removals_agrees_with_brute() {
+        let input = parse_input("test_data/day2.txt").unwrap();
+        for report in input {
+            assert_eq!(
+                is_safe_dumped_brute(&report, &SafetyRules::default()),
+                min_removals_default(&report, 1).is_some(),
+                "{:?}",
+                report
+            );
+        }
+    }
+
     #[test]
     fn evaluate_report_simple() {
         let input = parse_input("test_data/day2.txt").unwrap();
-        let r = evaluate_report(&input).unwrap();
+        let r = evaluate_report(&input, &SafetyRules::default()).unwrap();
         assert_eq!(2, r);
     }
 
     #[test]
     fn evaluate_report_dumped_simple() {
         let input = parse_input("test_data/day2.txt").unwrap();
-        let r = evaluate_reports_with_dumping(&input).unwrap();
+        let r = evaluate_reports_with_dumping(&input, &SafetyRules::default()).unwrap();
         assert_eq!(4, r);
     }
 
+    /// Mostly almost-safe reports: a random walk with small steps.
+    fn random_report(rng: &mut Rng) -> Vec<i32> {
+        let len = rng.range(0..=9) as usize;
+        let mut level = rng.range(1..=20);
+        let mut report = Vec::with_capacity(len);
+        for _ in 0..len {
+            report.push(level);
+            level += rng.range(-5..=5);
+        }
+        report
+    }
+
+    /// Greedily removes and shrinks levels while `fails` keeps holding.
+    fn shrink(mut report: Vec<i32>, fails: impl Fn(&[i32]) -> bool) -> Vec<i32> {
+        loop {
+            let removals = (0..report.len()).map(|i| dump_pos(&report, i));
+            let smaller = (0..report.len()).flat_map(|i| {
+                let report = &report;
+                [0, report[i] / 2, report[i] - report[i].signum()]
+                    .into_iter()
+                    .filter(move |v| *v != report[i])
+                    .map(move |v| {
+                        let mut r = report.clone();
+                        r[i] = v;
+                        r
+                    })
+            });
+            match removals.chain(smaller).find(|r| fails(r)) {
+                Some(r) => report = r,
+                None => return report,
+            }
+        }
+    }
+
+    fn fuzz(seed: u64, rules: &SafetyRules) {
+        let disagree = |r: &[i32]| is_safe_with_dumping(r, rules) != is_safe_dumped_brute(r, rules);
+        let mut rng = Rng::new(seed);
+        for _ in 0..20_000 {
+            let report = random_report(&mut rng);
+            if disagree(&report) {
+                let minimal = shrink(report.clone(), disagree);
+                panic!(
+                    "dampener disagrees with brute force on {:?}, minimal {:?}",
+                    report, minimal
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fuzz_dumping_against_brute() {
+        fuzz(0x2024_0002, &SafetyRules::default());
+    }
+
+    #[test]
+    fn fuzz_dumping_against_brute_custom_rules() {
+        fuzz(
+            0x2024_0003,
+            &SafetyRules {
+                step: 1..=4,
+                allow_equal: true,
+                direction: Direction::Either,
+                max_drift: Some(9),
+            },
+        );
+        fuzz(
+            0x2024_0004,
+            &SafetyRules {
+                direction: Direction::Decreasing,
+                ..SafetyRules::default()
+            },
+        );
+    }
+
+    #[test]
+    fn fuzz_min_removals_against_brute() {
+        let rule_sets = [
+            SafetyRules::default(),
+            SafetyRules {
+                step: 1..=4,
+                allow_equal: true,
+                direction: Direction::Either,
+                max_drift: Some(9),
+            },
+            SafetyRules {
+                step: 2..=5,
+                direction: Direction::Decreasing,
+                ..SafetyRules::default()
+            },
+            SafetyRules {
+                step: 0..=2,
+                direction: Direction::Increasing,
+                max_drift: Some(4),
+                ..SafetyRules::default()
+            },
+        ];
+        let mut rng = Rng::new(0x2024_0005);
+        for rules in &rule_sets {
+            for _ in 0..20_000 {
+                let report = random_report(&mut rng);
+                assert_eq!(
+                    is_safe_dumped_brute(&report, rules),
+                    min_removals(&report, rules, 1).is_some(),
+                    "{:?} {:?}",
+                    report,
+                    rules
+                );
+                assert_eq!(
+                    is_safe(&report, rules).is_ok(),
+                    min_removals(&report, rules, 0).is_some()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dumped_matches_brute() {
+        let input = parse_input("test_data/day2.txt").unwrap();
+        let rules = SafetyRules::default();
+        for report in &input {
+            assert_eq!(
+                is_safe_dumped_brute(report, &rules),
+                is_safe_with_dumping(report, &rules)
+            );
+        }
+    }
+
+    #[test]
+    fn min_removals_custom_rules() {
+        let rules = SafetyRules {
+            max_drift: Some(3),
+            ..SafetyRules::default()
+        };
+
+        assert_eq!(Some(vec![]), min_removals_default(&[1, 3, 5, 7], 0));
+        assert_eq!(Some(vec![2, 3]), min_removals(&[1, 3, 5, 7], &rules, 2));
+        assert_eq!(Some(vec![0]), min_removals(&[9, 1, 3, 4], &rules, 1));
+    }
+
+    fn min_removals_default(report: &[i32], tolerance: usize) -> Option<Vec<usize>> {
+        min_removals(report, &SafetyRules::default(), tolerance)
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let e = part1("7 6 4 2 1\n1 2 x 3\n").unwrap_err();
+
+        assert_eq!(
+            "line 2, column 5: invalid number \"x\" (invalid digit found in string)",
+            e.to_string()
+        );
+        assert_eq!(Err(e), part2("7 6 4 2 1\n1 2 x 3\n"));
+    }
+
+    #[test]
+    fn shrink_finds_minimal_report() {
+        let minimal = shrink(vec![7, 3, 12, 9, 4], |r| r.iter().any(|l| *l > 5));
+
+        assert_eq!(vec![6], minimal);
+    }
+
     fn parse_input(path: &str) -> Result<Vec<Vec<i32>>, Error> {
+        let input = load(path)?;
+        from(input.as_str())
+    }
+
+    fn load(path: &str) -> Result<String, Error> {
         let mut input = String::new();
         let mut input_file = File::open(path)?;
         input_file.read_to_string(&mut input)?;
-
-        let input = input.as_str();
-        Ok(from(input))
+        Ok(input)
     }
 }
@@ -1,33 +1,65 @@
 use std::{
-    io::{self},
-    num::ParseIntError,
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::{self, Display},
+    io::{self, Read},
+    ops::Range,
+};
+
+use crate::{
+    error::{Error, ParseErrorKind},
+    number::{Arithmetic, Number},
 };
 
 #[aoc(day3, part1)]
-pub fn part1(input: &str) -> i32 {
-    let computation = Computation::parse(input).unwrap();
-    computation.calculate()
+pub fn part1(input: &str) -> Result<i64, Error> {
+    let computation = Computation::parse(input)?;
+    computation.calculate(Arithmetic::Checked)
 }
 
 #[aoc(day3, part2)]
-pub fn part2(input: &str) -> i32 {
-    let computation = Computation::parse(input).unwrap();
-    computation.calculate_with_switches()
+pub fn part2(input: &str) -> Result<i64, Error> {
+    let computation = Computation::parse(input)?;
+    computation.calculate_with_switches(Arithmetic::Checked)
 }
 
-fn parse(input: &str) -> Result<Vec<Operation>, Error> {
-    let ops_pattern = regex_lite::Regex::new(r"mul\(([0-9]+),([0-9]+)\)|don't\(\)|do\(\)").unwrap();
+#[aoc(day3, part2, trace)]
+pub fn part2_trace(input: &str) -> Result<String, Error> {
+    let computation = Computation::parse(input)?;
+    Ok(format!("\n{}", computation.render_trace(input)?))
+}
+
+#[aoc(day3, part1, interpreter)]
+pub fn part1_interpreter(input: &str) -> Result<i64, Error> {
+    Ok(Interpreter::multiplications().run(input)?.total)
+}
+
+#[aoc(day3, part2, interpreter)]
+pub fn part2_interpreter(input: &str) -> Result<i64, Error> {
+    Ok(Interpreter::with_switches().run(input)?.total)
+}
+
+fn parse(input: &str) -> Result<Vec<Spanned>, Error> {
+    Scanner::new(input).collect()
+}
 
-    let mut result: Vec<Operation> = vec![];
+/// Reference implementation of [`Scanner`].
+#[cfg(test)]
+fn parse_regex(input: &str) -> Result<Vec<Spanned>, Error> {
+    let ops_pattern =
+        regex_lite::Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)|don't\(\)|do\(\)").unwrap();
+
+    let mut result: Vec<Spanned> = vec![];
     for c in ops_pattern.captures_iter(input) {
+        let span = c.get(0).unwrap().range();
         match &c[0] {
-            "do()" => result.push(Operation::Do()),
-            "don't()" => result.push(Operation::DoNot()),
+            "do()" => result.push(Spanned::new(Operation::Do(), span)),
+            "don't()" => result.push(Spanned::new(Operation::DoNot(), span)),
             _ => {
                 if let (Some(op1), Some(op2)) = (c.get(1), c.get(2)) {
                     let op1: i32 = op1.as_str().parse().unwrap();
                     let op2: i32 = op2.as_str().parse().unwrap();
-                    result.push(Operation::Multiply(op1, op2));
+                    result.push(Spanned::new(Operation::Multiply(op1, op2), span));
                 }
             }
         }
@@ -35,46 +67,226 @@ fn parse(input: &str) -> Result<Vec<Operation>, Error> {
 
     Ok(result)
 }
+/// Streams the operations of corrupted memory without allocating, `mul`
+/// operands have one to three digits unless configured otherwise.
+pub struct Scanner<'a> {
+    input: &'a [u8],
+    pos: usize,
+    max_digits: Option<usize>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Scanner::from_bytes(input.as_bytes())
+    }
+
+    fn from_bytes(input: &'a [u8]) -> Self {
+        Scanner {
+            input,
+            pos: 0,
+            max_digits: Some(3),
+        }
+    }
+
+    /// Longer operands make the instruction corrupted, `None` accepts any
+    /// length and reports operands that don't fit an `i32` as parse errors.
+    pub fn max_digits(mut self, max_digits: Option<usize>) -> Self {
+        self.max_digits = max_digits;
+        self
+    }
+
+    fn literal(&self, pos: usize, literal: &[u8]) -> Option<usize> {
+        self.input[pos..]
+            .starts_with(literal)
+            .then_some(pos + literal.len())
+    }
+
+    /// The digits of an operand starting at `pos`.
+    fn operand(&self, pos: usize) -> Option<Range<usize>> {
+        let mut end = pos;
+        while let Some(b'0'..=b'9') = self.input.get(end) {
+            if Some(end - pos) == self.max_digits {
+                return None;
+            }
+            end += 1;
+        }
+        (end > pos).then_some(pos..end)
+    }
+
+    fn value(&self, digits: Range<usize>) -> Result<i32, Error> {
+        let start = digits.start;
+        // operands only contain ascii digits
+        let token = String::from_utf8_lossy(&self.input[digits]);
+        token.parse().map_err(|source| {
+            // only on errors, finding the position scans the input before it
+            let (line, column) = position(self.input, start);
+            let kind = ParseErrorKind::Int {
+                token: token.to_string(),
+                source,
+            };
+            Error::parsing(line, column, kind)
+        })
+    }
+
+    fn multiply(&self, pos: usize) -> Option<(Result<Operation, Error>, usize)> {
+        let pos = self.literal(pos, b"mul(")?;
+        let op1 = self.operand(pos)?;
+        let pos = self.literal(op1.end, b",")?;
+        let op2 = self.operand(pos)?;
+        let pos = self.literal(op2.end, b")")?;
+        let op = self
+            .value(op1)
+            .and_then(|op1| Ok(Operation::Multiply(op1, self.value(op2)?)));
+        Some((op, pos))
+    }
+
+    fn operation_at(&self, pos: usize) -> Option<(Result<Operation, Error>, usize)> {
+        match self.input[pos] {
+            b'm' => self.multiply(pos),
+            b'd' => self
+                .literal(pos, b"don't()")
+                .map(|end| (Ok(Operation::DoNot()), end))
+                .or_else(|| {
+                    self.literal(pos, b"do()")
+                        .map(|end| (Ok(Operation::Do()), end))
+                }),
+            _ => None,
+        }
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Result<Spanned, Error>;
+
+    fn next(&mut self) -> Option<Result<Spanned, Error>> {
+        while self.pos < self.input.len() {
+            let candidate = self.input[self.pos..]
+                .iter()
+                .position(|b| *b == b'm' || *b == b'd')?;
+            self.pos += candidate;
+            if let Some((op, end)) = self.operation_at(self.pos) {
+                let span = self.pos..end;
+                self.pos = end;
+                return Some(op.map(|op| Spanned::new(op, span)));
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+/// 1-based line and column of the byte at `offset`.
+fn position(input: &[u8], offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line_start = before
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |newline| newline + 1);
+    let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+    (line, offset - line_start + 1)
+}
+
 #[derive(Debug, PartialEq)]
-enum Operation {
+pub enum Operation {
     Multiply(i32, i32),
     Do(),
     DoNot(),
 }
 
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Multiply(op1, op2) => write!(f, "mul({},{})", op1, op2),
+            Operation::Do() => write!(f, "do()"),
+            Operation::DoNot() => write!(f, "don't()"),
+        }
+    }
+}
+
+/// An [`Operation`] and the bytes of the input it was parsed from.
+#[derive(Debug, PartialEq)]
+pub struct Spanned {
+    pub op: Operation,
+    pub span: Range<usize>,
+}
+
+impl Spanned {
+    fn new(op: Operation, span: Range<usize>) -> Self {
+        Spanned { op, span }
+    }
+}
+
+/// State of [`Computation::calculate_with_switches`] after an operation.
+#[derive(Debug, PartialEq)]
+struct TraceStep {
+    span: Range<usize>,
+    enabled: bool,
+    total: i64,
+}
+
 struct Computation {
-    ops: Vec<Operation>,
+    ops: Vec<Spanned>,
 }
 
 impl Computation {
-    fn calculate(&self) -> i32 {
-        self.ops
-            .iter()
-            .map(|op| match op {
-                Operation::Multiply(a, b) => a * b,
-                Operation::Do() => 0,
-                Operation::DoNot() => 0,
-            })
-            .sum()
+    fn calculate<T: Number + From<i32>>(&self, arithmetic: Arithmetic) -> Result<T, Error> {
+        self.run(false, arithmetic, |_, _, _| {})
+    }
+
+    fn calculate_with_switches<T: Number + From<i32>>(
+        &self,
+        arithmetic: Arithmetic,
+    ) -> Result<T, Error> {
+        self.run(true, arithmetic, |_, _, _| {})
     }
 
-    fn calculate_with_switches(&self) -> i32 {
-        let mut enable = true;
-        let mut result = 0;
+    /// Evaluates all operations, calling `step` with each operation, whether
+    /// multiplications are enabled after it and the running total.
+    fn run<T: Number + From<i32>>(
+        &self,
+        switches: bool,
+        arithmetic: Arithmetic,
+        mut step: impl FnMut(&Spanned, bool, T),
+    ) -> Result<T, Error> {
+        let mut evaluator = Evaluator::new(switches, arithmetic);
         for op in self.ops.iter() {
-            match op {
-                Operation::Multiply(op1, op2) => {
-                    if enable {
-                        result += *op1 * *op2
-                    }
-                }
-                Operation::Do() => enable = true,
-                Operation::DoNot() => enable = false,
-            }
+            evaluator.apply(op)?;
+            step(op, evaluator.enabled, evaluator.total);
         }
-        result
+        Ok(evaluator.total)
+    }
+
+    fn trace(&self) -> Result<Vec<TraceStep>, Error> {
+        let mut steps = vec![];
+        self.run(true, Arithmetic::Checked, |op, enabled, total| {
+            steps.push(TraceStep {
+                span: op.span.clone(),
+                enabled,
+                total,
+            })
+        })?;
+        Ok(steps)
+    }
+
+    /// One line per operation: its span, source text, whether multiplications
+    /// are enabled after it and the running total.
+    fn render_trace(&self, input: &str) -> Result<String, Error> {
+        Ok(self
+            .trace()?
+            .into_iter()
+            .map(|step| {
+                format!(
+                    "{:>8}  {:<12} {:<8} {}\n",
+                    format!("{}..{}", step.span.start, step.span.end),
+                    &input[step.span],
+                    if step.enabled { "enabled" } else { "disabled" },
+                    step.total
+                )
+            })
+            .collect())
     }
-    fn from(ops: Vec<Operation>) -> Self {
+
+    fn from(ops: Vec<Spanned>) -> Self {
         Computation { ops }
     }
 
@@ -83,32 +295,439 @@ impl Computation {
         ops.map(Computation::from)
     }
 }
-#[derive(Debug)]
-pub enum Error {
-    InputError(io::Error),
-    ParsingInput(ParseIntError),
+
+/// Sums the products of the enabled multiplications in `T`, checked
+/// arithmetic reports the instruction the total overflowed at.
+struct Evaluator<T> {
+    switches: bool,
+    arithmetic: Arithmetic,
+    enabled: bool,
+    total: T,
 }
 
-impl From<io::Error> for Error {
-    fn from(value: io::Error) -> Self {
-        Error::InputError(value)
+impl<T: Number + From<i32>> Evaluator<T> {
+    fn new(switches: bool, arithmetic: Arithmetic) -> Self {
+        Evaluator {
+            switches,
+            arithmetic,
+            enabled: true,
+            total: T::ZERO,
+        }
     }
+
+    fn apply(&mut self, op: &Spanned) -> Result<(), Error> {
+        match op.op {
+            Operation::Multiply(op1, op2) if self.enabled => {
+                self.total = self
+                    .arithmetic
+                    .mul(T::from(op1), T::from(op2))
+                    .and_then(|product| self.arithmetic.add(self.total, product))
+                    .ok_or_else(|| {
+                        Error::Overflow(format!("total at {} (byte {})", op.op, op.span.start))
+                    })?;
+            }
+            Operation::Multiply(..) => {}
+            Operation::Do() if self.switches => self.enabled = true,
+            Operation::DoNot() if self.switches => self.enabled = false,
+            Operation::Do() | Operation::DoNot() => {}
+        }
+        Ok(())
+    }
+}
+
+/// Longest instruction the [`Scanner`] matches with its default operand length.
+const MAX_INSTRUCTION_LEN: usize = "mul(123,123)".len();
+
+/// Streaming counterpart of [`Computation::calculate`], `input` is read
+/// `chunk_len` bytes at a time.
+pub fn calculate_streaming<T, R>(
+    input: R,
+    chunk_len: usize,
+    arithmetic: Arithmetic,
+) -> Result<T, Error>
+where
+    T: Number + From<i32>,
+    R: Read,
+{
+    evaluate_streaming(input, chunk_len, Evaluator::new(false, arithmetic))
+}
+
+/// Streaming counterpart of [`Computation::calculate_with_switches`].
+pub fn calculate_with_switches_streaming<T, R>(
+    input: R,
+    chunk_len: usize,
+    arithmetic: Arithmetic,
+) -> Result<T, Error>
+where
+    T: Number + From<i32>,
+    R: Read,
+{
+    evaluate_streaming(input, chunk_len, Evaluator::new(true, arithmetic))
+}
+
+/// Scans each chunk appended to the unscanned rest of the previous ones.
+/// Operations starting in the last `MAX_INSTRUCTION_LEN - 1` bytes may be cut
+/// off, so they are left for the next round unless the input has ended.
+fn evaluate_streaming<T, R>(
+    mut input: R,
+    chunk_len: usize,
+    mut evaluator: Evaluator<T>,
+) -> Result<T, Error>
+where
+    T: Number + From<i32>,
+    R: Read,
+{
+    let mut chunk = vec![0; chunk_len.max(1)];
+    let mut pending: Vec<u8> = Vec::new();
+    // position of `pending` in the whole input
+    let mut offset = 0;
+    loop {
+        let read = match input.read(&mut chunk) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            read => read?,
+        };
+        pending.extend_from_slice(&chunk[..read]);
+        let complete = if read == 0 {
+            pending.len()
+        } else {
+            pending.len().saturating_sub(MAX_INSTRUCTION_LEN - 1)
+        };
+
+        let mut scanned = complete;
+        for op in Scanner::from_bytes(&pending) {
+            let Spanned { op, span } = op?;
+            if span.start >= complete {
+                break;
+            }
+            scanned = scanned.max(span.end);
+            evaluator.apply(&Spanned::new(op, span.start + offset..span.end + offset))?;
+        }
+
+        if read == 0 {
+            return Ok(evaluator.total);
+        }
+        pending.drain(..scanned);
+        offset += scanned;
+    }
+}
+
+/// Behavior of an [`Instruction`], gets the evaluated operands and returns
+/// the instruction's value or `None` if it can't be applied (e.g. division by
+/// zero), in which case the instruction counts as corrupted.
+pub type Op = Box<dyn Fn(&mut State, &[Operand]) -> Option<i64>>;
+
+pub struct Instruction {
+    name: String,
+    arity: usize,
+    accumulate: bool,
+    op: Op,
+}
+
+impl Instruction {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        op: impl Fn(&mut State, &[Operand]) -> Option<i64> + 'static,
+    ) -> Self {
+        Instruction {
+            name: name.to_string(),
+            arity,
+            accumulate: false,
+            op: Box::new(op),
+        }
+    }
+
+    /// Values of top-level calls are added to the total while enabled.
+    pub fn accumulate(mut self) -> Self {
+        self.accumulate = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Operand {
+    pub value: i64,
+    /// Set if the operand was a register reference.
+    pub register: Option<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct State {
+    pub enabled: bool,
+    pub total: i64,
+    pub registers: HashMap<u8, i64>,
 }
 
-impl From<ParseIntError> for Error {
-    fn from(value: ParseIntError) -> Self {
-        Error::ParsingInput(value)
+impl Default for State {
+    fn default() -> Self {
+        State {
+            enabled: true,
+            total: 0,
+            registers: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Arg {
+    Number(i64),
+    Register(u8),
+    Call(Call),
+}
+
+#[derive(Debug, PartialEq)]
+struct Call {
+    instruction: usize,
+    args: Vec<Arg>,
+}
+
+/// Interpreter for the corrupted memory language. Instructions look like
+/// `name(arg,...)` without any whitespace; everything that doesn't parse as a
+/// registered instruction is skipped byte by byte.
+#[derive(Default)]
+pub struct Interpreter {
+    instructions: Vec<Instruction>,
+    /// Operands may be calls of other instructions.
+    pub nested: bool,
+    /// Operands may be registers, a single lowercase letter.
+    pub registers: bool,
+    /// Longest number literal accepted, unlimited if `None`.
+    pub max_digits: Option<usize>,
+    /// Deepest nesting of calls in operands, anything deeper is treated as
+    /// corrupted.
+    pub max_depth: usize,
+}
+
+impl Interpreter {
+    /// Adds `instruction`, replacing one registered under the same name.
+    pub fn register(&mut self, instruction: Instruction) -> &mut Self {
+        self.instructions.retain(|i| i.name != instruction.name);
+        self.instructions.push(instruction);
+        // longer names first, so `don't` is preferred over a `don` instruction
+        self.instructions.sort_by_key(|i| Reverse(i.name.len()));
+        self
+    }
+
+    /// Same result as `Computation::calculate`.
+    pub fn multiplications() -> Self {
+        let mut interpreter = Interpreter {
+            max_digits: Some(3),
+            max_depth: 32,
+            ..Interpreter::default()
+        };
+        interpreter
+            .register(
+                Instruction::new("mul", 2, |_, a| a[0].value.checked_mul(a[1].value)).accumulate(),
+            )
+            .register(Instruction::new("do", 0, |_, _| Some(0)))
+            .register(Instruction::new("don't", 0, |_, _| Some(0)));
+        interpreter
+    }
+
+    /// Same result as `Computation::calculate_with_switches`.
+    pub fn with_switches() -> Self {
+        let mut interpreter = Interpreter::multiplications();
+        interpreter
+            .register(Instruction::new("do", 0, |state, _| {
+                state.enabled = true;
+                Some(0)
+            }))
+            .register(Instruction::new("don't", 0, |state, _| {
+                state.enabled = false;
+                Some(0)
+            }));
+        interpreter
+    }
+
+    /// [`Interpreter::with_switches`] plus `add`, `sub`, `div`, nested calls,
+    /// registers, set with `set(r,value)`, and numbers of any length.
+    pub fn extended() -> Self {
+        let mut interpreter = Interpreter::with_switches();
+        interpreter.nested = true;
+        interpreter.registers = true;
+        interpreter.max_digits = None;
+        interpreter
+            .register(
+                Instruction::new("add", 2, |_, a| a[0].value.checked_add(a[1].value)).accumulate(),
+            )
+            .register(
+                Instruction::new("sub", 2, |_, a| a[0].value.checked_sub(a[1].value)).accumulate(),
+            )
+            .register(
+                Instruction::new("div", 2, |_, a| a[0].value.checked_div(a[1].value)).accumulate(),
+            )
+            .register(Instruction::new("set", 2, |state, a| {
+                let register = a[0].register?;
+                state.registers.insert(register, a[1].value);
+                Some(a[1].value)
+            }));
+        interpreter
+    }
+
+    pub fn run(&self, input: &str) -> Result<State, Error> {
+        let mut state = State::default();
+        for (offset, call) in self.parse(input.as_bytes()) {
+            let instruction = &self.instructions[call.instruction];
+            if let Some(value) = self.eval(&call, &mut state) {
+                if instruction.accumulate && state.enabled {
+                    state.total = state.total.checked_add(value).ok_or_else(|| {
+                        Error::Overflow(format!("total at {} (byte {})", instruction.name, offset))
+                    })?;
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    fn eval(&self, call: &Call, state: &mut State) -> Option<i64> {
+        let mut operands = Vec::with_capacity(call.args.len());
+        for arg in &call.args {
+            operands.push(match arg {
+                Arg::Number(value) => Operand {
+                    value: *value,
+                    register: None,
+                },
+                Arg::Register(r) => Operand {
+                    value: *state.registers.get(r).unwrap_or(&0),
+                    register: Some(*r),
+                },
+                Arg::Call(call) => Operand {
+                    value: self.eval(call, state)?,
+                    register: None,
+                },
+            });
+        }
+        (self.instructions[call.instruction].op)(state, &operands)
+    }
+
+    fn parse(&self, input: &[u8]) -> Vec<(usize, Call)> {
+        let mut calls = Vec::new();
+        let mut pos = 0;
+        while pos < input.len() {
+            let mut cursor = Cursor { input, pos };
+            match self.call(&mut cursor, 0) {
+                Some(call) => {
+                    calls.push((pos, call));
+                    pos = cursor.pos;
+                }
+                None => pos += 1,
+            }
+        }
+        calls
+    }
+
+    fn call(&self, cursor: &mut Cursor, depth: usize) -> Option<Call> {
+        let start = cursor.pos;
+        for (idx, instruction) in self.instructions.iter().enumerate() {
+            cursor.pos = start;
+            if let Some(args) = self.args(cursor, instruction, depth) {
+                return Some(Call {
+                    instruction: idx,
+                    args,
+                });
+            }
+        }
+        cursor.pos = start;
+        None
+    }
+
+    fn args(
+        &self,
+        cursor: &mut Cursor,
+        instruction: &Instruction,
+        depth: usize,
+    ) -> Option<Vec<Arg>> {
+        cursor.name(&instruction.name)?;
+        cursor.byte(b'(')?;
+        let mut args = Vec::with_capacity(instruction.arity);
+        for idx in 0..instruction.arity {
+            if idx > 0 {
+                cursor.byte(b',')?;
+            }
+            args.push(self.arg(cursor, depth)?);
+        }
+        cursor.byte(b')')?;
+        Some(args)
+    }
+
+    fn arg(&self, cursor: &mut Cursor, depth: usize) -> Option<Arg> {
+        if let Some(value) = cursor.number(self.max_digits) {
+            return Some(Arg::Number(value));
+        }
+        if self.nested && depth < self.max_depth {
+            if let Some(call) = self.call(cursor, depth + 1) {
+                return Some(Arg::Call(call));
+            }
+        }
+        if self.registers {
+            if let Some(r) = cursor.register() {
+                return Some(Arg::Register(r));
+            }
+        }
+        None
+    }
+}
+
+/// Tokenizer over the raw bytes, every method consumes a token or leaves the
+/// position untouched.
+struct Cursor<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn name(&mut self, name: &str) -> Option<()> {
+        if self.input[self.pos..].starts_with(name.as_bytes()) {
+            self.pos += name.len();
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn byte(&mut self, expected: u8) -> Option<()> {
+        if self.input.get(self.pos) == Some(&expected) {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self, max_digits: Option<usize>) -> Option<i64> {
+        let digits = self.input[self.pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if digits == 0 || max_digits.is_some_and(|max| digits > max) {
+            return None;
+        }
+        let value = std::str::from_utf8(&self.input[self.pos..self.pos + digits])
+            .ok()?
+            .parse()
+            .ok()?;
+        self.pos += digits;
+        Some(value)
+    }
+
+    fn register(&mut self) -> Option<u8> {
+        let r = *self.input.get(self.pos)?;
+        if r.is_ascii_lowercase() && matches!(self.input.get(self.pos + 1), Some(b',' | b')')) {
+            self.pos += 1;
+            Some(r)
+        } else {
+            None
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use std::fs::File;
-
-    use io::Read;
+    use std::{fs::File, io::Read};
 
     use super::*;
+    use crate::test_rng::Rng;
 
     #[test]
     fn part_one_input_test() {
@@ -122,7 +741,7 @@ mod tests {
     #[test]
     fn part_one_parse() {
         let input = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
-        let parsed_input: Result<Vec<Operation>, Error> = parse(input);
+        let parsed_input: Result<Vec<Operation>, Error> = parse(input).map(operations);
         assert_eq!(
             parsed_input.unwrap(),
             vec![
@@ -136,7 +755,7 @@ mod tests {
     #[test]
     fn part_two_parse() {
         let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
-        let parsed_input: Result<Vec<Operation>, Error> = parse(input);
+        let parsed_input: Result<Vec<Operation>, Error> = parse(input).map(operations);
         assert_eq!(
             parsed_input.unwrap(),
             vec![
@@ -149,22 +768,197 @@ mod tests {
             ]
         );
     }
+    #[test]
+    fn scanner_operand_digits() {
+        let ops: Result<Vec<Spanned>, Error> =
+            Scanner::new("mul(1234,5)mul(123,45)mul(,1)mul(1,)mul(007,1)").collect();
+
+        assert_eq!(
+            vec![
+                Spanned::new(Operation::Multiply(123, 45), 11..22),
+                Spanned::new(Operation::Multiply(7, 1), 36..46)
+            ],
+            ops.unwrap()
+        );
+    }
+
+    #[test]
+    fn scanner_unbounded_operands() {
+        let ops: Result<Vec<Spanned>, Error> =
+            Scanner::new("mul(1234,5)").max_digits(None).collect();
+        assert_eq!(
+            vec![Spanned::new(Operation::Multiply(1234, 5), 0..11)],
+            ops.unwrap()
+        );
+
+        let e = Scanner::new("mul(1,2)\nxmul(3,99999999999)")
+            .max_digits(None)
+            .collect::<Result<Vec<Spanned>, Error>>()
+            .unwrap_err();
+        assert_eq!(
+            "line 2, column 8: invalid number \"99999999999\" (number too large to fit in target type)",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn scanner_large_input() {
+        // quadratic scanning would take minutes here
+        let input = "mul(1,2)\n".repeat(200_000);
+        let count = Scanner::new(&input)
+            .max_digits(None)
+            .filter(Result::is_ok)
+            .count();
+
+        assert_eq!(200_000, count);
+    }
+
+    #[test]
+    fn scanner_matches_regex_on_random_memory() {
+        const ALPHABET: &[u8] = b"mul(),don't0123456789x _";
+        const FRAGMENTS: [&str; 6] = ["mul(", "do()", "don't()", "mul(12,3)", "1234", "mumul(1,2"];
+        let mut rng = Rng::new(0x2024_0003);
+        for _ in 0..2_000 {
+            let mut input = String::new();
+            for _ in 0..rng.below(80) {
+                if rng.below(4) == 0 {
+                    input.push_str(FRAGMENTS[rng.below(FRAGMENTS.len())]);
+                } else {
+                    input.push(ALPHABET[rng.below(ALPHABET.len())] as char);
+                }
+            }
+            assert_eq!(
+                parse_regex(&input).unwrap(),
+                parse(&input).unwrap(),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn scanner_matches_regex_on_input() {
+        let input = read_input("input/2024/day3.txt").unwrap();
+
+        assert_eq!(parse_regex(&input).unwrap(), parse(&input).unwrap());
+    }
+
     #[test]
     fn operations_calculate() {
-        let cmps = Computation::from(vec![
+        let cmps = Computation::from(spanned(vec![
             Operation::Multiply(2, 4),
             Operation::Multiply(5, 5),
             Operation::Multiply(11, 8),
             Operation::Multiply(8, 5),
-        ]);
-        assert_eq!(cmps.calculate(), 161);
+        ]));
+        assert_eq!(cmps.calculate::<i32>(Arithmetic::Checked).unwrap(), 161);
+    }
+
+    #[test]
+    fn calculate_accumulators() {
+        let input = "mul(2147483647,2)don't()mul(2147483647,2147483647)";
+        let ops: Result<Vec<Spanned>, Error> = Scanner::new(input).max_digits(None).collect();
+        let comp = Computation::from(ops.unwrap());
+
+        assert_eq!(
+            "total at mul(2147483647,2) (byte 0) overflowed",
+            comp.calculate::<i32>(Arithmetic::Checked)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            Ok(i32::MAX),
+            comp.calculate_with_switches::<i32>(Arithmetic::Saturating)
+        );
+        assert_eq!(
+            Ok(4294967294),
+            comp.calculate_with_switches::<i64>(Arithmetic::Checked)
+        );
+        assert_eq!(
+            Ok(4611686014132420609 + 4294967294),
+            comp.calculate::<i64>(Arithmetic::Checked)
+        );
+    }
+
+    #[test]
+    fn trace_switches() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let comp = Computation::parse(input).unwrap();
+
+        assert_eq!(
+            "    1..9  mul(2,4)     enabled  8
+  20..27  don't()      disabled 8
+  28..36  mul(5,5)     disabled 8
+  48..57  mul(11,8)    disabled 8
+  59..63  do()         enabled  8
+  64..72  mul(8,5)     enabled  48
+",
+            comp.render_trace(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn trace_total_matches_calculate() {
+        let input = read_input("input/2024/day3.txt").unwrap();
+        let comp = Computation::parse(&input).unwrap();
+
+        let last = comp.trace().unwrap().pop().unwrap();
+        assert_eq!(
+            comp.calculate_with_switches(Arithmetic::Checked),
+            Ok(last.total)
+        );
+    }
+
+    #[test]
+    fn streaming_matches_in_memory() {
+        let test_data = read_input("test_data/day3.txt").unwrap();
+        let input = read_input("input/2024/day3.txt").unwrap();
+        let switches = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+        for input in [test_data.as_str(), switches, input.as_str()] {
+            let comp = Computation::parse(input).unwrap();
+            let expected = comp.calculate::<i64>(Arithmetic::Checked);
+            let expected_switches = comp.calculate_with_switches::<i64>(Arithmetic::Checked);
+            for chunk_len in (1..=MAX_INSTRUCTION_LEN + 1).chain([64, 4096]) {
+                assert_eq!(
+                    expected,
+                    calculate_streaming(input.as_bytes(), chunk_len, Arithmetic::Checked)
+                );
+                assert_eq!(
+                    expected_switches,
+                    calculate_with_switches_streaming(
+                        input.as_bytes(),
+                        chunk_len,
+                        Arithmetic::Checked
+                    )
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_instruction_across_chunks() {
+        // the first chunk ends in `mul(`, the operands are in the next one
+        let input = "xxxxxxxxmul(999,999)mul(1,1)mumul(2,2)";
+        assert_eq!(
+            Ok(999 * 999 + 1 + 4),
+            calculate_streaming::<i32, _>(input.as_bytes(), 12, Arithmetic::Checked)
+        );
+
+        let input = "mul(999,999)".repeat(2200);
+        assert_eq!(
+            "total at mul(999,999) (byte 25812) overflowed",
+            calculate_streaming::<i32, _>(input.as_bytes(), 7, Arithmetic::Checked)
+                .unwrap_err()
+                .to_string()
+        );
     }
 
     #[test]
     fn part_one_input_test_complete() {
         let input = read_input("test_data/day3.txt").unwrap();
         let comp = Computation::parse(input.as_str());
-        let result = comp.map(|comp| comp.calculate());
+        let result = comp.and_then(|comp| comp.calculate::<i32>(Arithmetic::Checked));
         assert_eq!(161, result.unwrap());
     }
 
@@ -173,7 +967,7 @@ mod tests {
         let input =
             "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))".to_string();
         let comp = Computation::parse(input.as_str());
-        let result = comp.map(|comp| comp.calculate_with_switches());
+        let result = comp.and_then(|comp| comp.calculate_with_switches::<i32>(Arithmetic::Checked));
         assert_eq!(48, result.unwrap());
     }
     #[test]
@@ -182,11 +976,93 @@ mod tests {
 
         let comp = Computation::parse(input.as_str())?;
 
-        println!("Compute {}", comp.calculate());
-        println!("Compute {}", comp.calculate_with_switches());
+        println!("Compute {}", comp.calculate::<i64>(Arithmetic::Checked)?);
+        println!(
+            "Compute {}",
+            comp.calculate_with_switches::<i64>(Arithmetic::Checked)?
+        );
 
         Ok(())
     }
+    #[test]
+    fn interpreter_presets_match_computation() {
+        for path in ["test_data/day3.txt", "input/2024/day3.txt"] {
+            let input = read_input(path).unwrap();
+            let comp = Computation::parse(input.as_str()).unwrap();
+
+            assert_eq!(
+                comp.calculate::<i64>(Arithmetic::Checked).unwrap(),
+                Interpreter::multiplications().run(&input).unwrap().total
+            );
+            assert_eq!(
+                comp.calculate_with_switches::<i64>(Arithmetic::Checked)
+                    .unwrap(),
+                Interpreter::with_switches().run(&input).unwrap().total
+            );
+        }
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(48, Interpreter::with_switches().run(input).unwrap().total);
+    }
+
+    #[test]
+    fn interpreter_presets_ignore_extensions() {
+        let input = "mul(mul(2,3),4)add(1,2)mul(a,2)";
+
+        assert_eq!(6, Interpreter::with_switches().run(input).unwrap().total);
+    }
+
+    #[test]
+    fn interpreter_extended() {
+        let input = "xadd(1,2)mul(add(1,2),4)don't()sub(10,3)do()div(9,0)set(a,5)mul(a,a)div(a,2)";
+        let state = Interpreter::extended().run(input).unwrap();
+
+        assert_eq!(3 + 12 + 25 + 2, state.total);
+        assert_eq!(Some(&5), state.registers.get(&b'a'));
+    }
+
+    #[test]
+    fn interpreter_custom_instruction() {
+        let mut interpreter = Interpreter::multiplications();
+        interpreter.register(Instruction::new("neg", 1, |_, a| Some(-a[0].value)).accumulate());
+
+        let state = interpreter.run("neg(5)mul(1000,1)mul(2,3)neg(x)").unwrap();
+        assert_eq!(1, state.total);
+    }
+
+    #[test]
+    fn interpreter_nesting_depth() {
+        let mut interpreter = Interpreter::extended();
+        interpreter.max_depth = 1;
+
+        let state = interpreter.run("add(add(1,2),add(add(1,1),1))").unwrap();
+        assert_eq!(3 + 3, state.total);
+
+        let state = Interpreter::extended()
+            .run(&"mul(".repeat(100_000))
+            .unwrap();
+        assert_eq!(0, state.total);
+    }
+
+    #[test]
+    fn interpreter_overflow() {
+        let e = Interpreter::extended()
+            .run("add(9223372036854775807,0)add(1,0)")
+            .unwrap_err();
+
+        assert_eq!("total at add (byte 26) overflowed", e.to_string());
+    }
+
+    fn operations(ops: Vec<Spanned>) -> Vec<Operation> {
+        ops.into_iter().map(|s| s.op).collect()
+    }
+
+    fn spanned(ops: Vec<Operation>) -> Vec<Spanned> {
+        ops.into_iter()
+            .enumerate()
+            .map(|(i, op)| Spanned::new(op, i..i + 1))
+            .collect()
+    }
+
     fn read_input(path: &str) -> Result<String, Error> {
         let mut input = String::new();
         let mut input_file = File::open(path)?;
@@ -1,107 +1,515 @@
-use std::io::{self};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::OnceLock,
+};
+
+use crate::error::{Error, ParseErrorKind};
 
 #[aoc(day4, part1)]
-pub fn part1(input: &str) -> usize {
-    let puzzle = parse_input(input).unwrap();
-    puzzle.count_xmas()
+pub fn part1(input: &str) -> Result<usize, Error> {
+    let puzzle = parse_input(input)?;
+    Ok(puzzle.count_xmas())
+}
+
+#[aoc(day4, part1, render)]
+pub fn part1_render(input: &str) -> Result<String, Error> {
+    let puzzle = parse_input(input)?;
+    let matches = puzzle.find(&WordSearch::new(&[XMAS]));
+    Ok(format!("\n{}", puzzle.render(&matches, false)))
 }
 
 #[aoc(day4, part2)]
-pub fn part2(input: &str) -> usize {
-    let puzzle = parse_input(input).unwrap();
-    puzzle.count_crossed_mas()
+pub fn part2(input: &str) -> Result<usize, Error> {
+    let puzzle = parse_input(input)?;
+    Ok(puzzle.count_crossed_mas())
 }
 
+/// Rectangular grid of ASCII letters, stored row after row.
+#[derive(Debug)]
 pub struct Puzzle {
-    data: Vec<Vec<char>>,
+    data: Vec<u8>,
+    rows: usize,
+    columns: usize,
+}
+
+/// Direction a word is read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Down,
+    Right,
+    DownRight,
+    Left,
+    Up,
+    DownLeft,
+    UpLeft,
+    UpRight,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::Down,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Left,
+        Direction::Up,
+        Direction::DownLeft,
+        Direction::UpLeft,
+        Direction::UpRight,
+    ];
+
+    /// ANSI escape sequence for matches read in the direction.
+    fn color(self) -> &'static str {
+        match self {
+            Direction::Down => "\x1b[31m",
+            Direction::Right => "\x1b[32m",
+            Direction::DownRight => "\x1b[33m",
+            Direction::Left => "\x1b[34m",
+            Direction::Up => "\x1b[35m",
+            Direction::DownLeft => "\x1b[36m",
+            Direction::UpLeft => "\x1b[91m",
+            Direction::UpRight => "\x1b[92m",
+        }
+    }
+
+    /// Row and column offset to the next letter.
+    pub fn step(self) -> (isize, isize) {
+        match self {
+            Direction::Down => (1, 0),
+            Direction::Right => (0, 1),
+            Direction::DownRight => (1, 1),
+            Direction::Left => (0, -1),
+            Direction::Up => (-1, 0),
+            Direction::DownLeft => (1, -1),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (-1, 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlap {
+    /// Every occurrence counts, letters may be shared.
+    Allowed,
+    /// Occurrences are taken in row-major order of their first letter and
+    /// skipped if they share a letter with one taken before.
+    Disjoint,
+}
+
+/// Words to look for on a [`Puzzle`], see [`Puzzle::find`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordSearch<'a> {
+    pub words: Vec<&'a str>,
+    pub directions: Vec<Direction>,
+    pub overlap: Overlap,
+}
+
+impl<'a> WordSearch<'a> {
+    /// Searches `words` in all directions, overlapping occurrences count.
+    pub fn new(words: &[&'a str]) -> Self {
+        WordSearch {
+            words: words.to_vec(),
+            directions: Direction::ALL.to_vec(),
+            overlap: Overlap::Allowed,
+        }
+    }
+
+    /// Number of matches of each word.
+    pub fn counts(&self, matches: &[Match]) -> Vec<usize> {
+        let mut counts = vec![0; self.words.len()];
+        for m in matches {
+            counts[m.word] += 1;
+        }
+        counts
+    }
+
+    /// Drops the matches `overlap` excludes from all matches in search order.
+    fn select(&self, matches: Vec<Match>) -> Vec<Match> {
+        if self.overlap == Overlap::Allowed {
+            return matches;
+        }
+        let mut used = HashSet::new();
+        matches
+            .into_iter()
+            .filter(|found| {
+                if found.cells().any(|cell| used.contains(&cell)) {
+                    return false;
+                }
+                used.extend(found.cells());
+                true
+            })
+            .collect()
+    }
+}
+
+/// Aho-Corasick automaton recognizing a list of words in a stream of letters.
+struct Automaton {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    /// Index and length of the words ending in each state.
+    output: Vec<Vec<(usize, usize)>>,
+}
+
+impl Automaton {
+    fn new(words: &[&str]) -> Self {
+        let mut automaton = Automaton {
+            goto: vec![HashMap::new()],
+            fail: vec![0],
+            output: vec![vec![]],
+        };
+        for (word, text) in words.iter().enumerate() {
+            if text.is_empty() {
+                continue;
+            }
+            let mut state = 0;
+            for c in text.chars() {
+                state = match automaton.goto[state].get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        automaton.goto.push(HashMap::new());
+                        automaton.fail.push(0);
+                        automaton.output.push(vec![]);
+                        let next = automaton.goto.len() - 1;
+                        automaton.goto[state].insert(c, next);
+                        next
+                    }
+                };
+            }
+            automaton.output[state].push((word, text.chars().count()));
+        }
+
+        // breadth first, so the failure state of a parent is always final
+        let mut queue: VecDeque<usize> = automaton.goto[0].values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<(char, usize)> = automaton.goto[state]
+                .iter()
+                .map(|(c, s)| (*c, *s))
+                .collect();
+            for (c, next) in edges {
+                let fail = automaton.next(automaton.fail[state], c);
+                automaton.fail[next] = fail;
+                let inherited = automaton.output[fail].clone();
+                automaton.output[next].extend(inherited);
+                queue.push_back(next);
+            }
+        }
+        automaton
+    }
+
+    fn next(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.goto[state].get(&c) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+}
+
+/// Occurrence of `words[word]` of a [`WordSearch`], starting at `row`,
+/// `column`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match {
+    pub word: usize,
+    pub row: usize,
+    pub column: usize,
+    pub direction: Direction,
+    pub len: usize,
+}
+
+impl Match {
+    /// Positions of the letters of the word.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (dr, dc) = self.direction.step();
+        (0..self.len as isize).map(move |i| {
+            (
+                self.row.wrapping_add_signed(dr * i),
+                self.column.wrapping_add_signed(dc * i),
+            )
+        })
+    }
+}
+
+/// A 2D shape of letters, `.` matches any letter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Template {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl Template {
+    /// One line per row, short rows are padded with wildcards. An empty
+    /// pattern matches nowhere.
+    pub fn parse(pattern: &str) -> Self {
+        let mut cells: Vec<Vec<Option<char>>> = pattern
+            .lines()
+            .map(|line| line.chars().map(|c| (c != '.').then_some(c)).collect())
+            .collect();
+        let width = cells.iter().map(Vec::len).max().unwrap_or(0);
+        for row in cells.iter_mut() {
+            row.resize(width, None);
+        }
+        Template { cells }
+    }
+
+    fn width(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    /// The template turned a quarter clockwise.
+    pub fn rotate(&self) -> Self {
+        let height = self.cells.len();
+        let cells = (0..self.width())
+            .map(|c_idx| {
+                (0..height)
+                    .map(|r_idx| self.cells[height - 1 - r_idx][c_idx])
+                    .collect()
+            })
+            .collect();
+        Template { cells }
+    }
+
+    /// The template mirrored left to right.
+    pub fn reflect(&self) -> Self {
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+        Template { cells }
+    }
+
+    /// The distinct rotations and reflections of the template, starting with
+    /// the template itself.
+    pub fn variants(&self) -> Vec<Template> {
+        let mut variants: Vec<Template> = vec![];
+        for start in [self.clone(), self.reflect()] {
+            let mut template = start;
+            for _ in 0..4 {
+                let next = template.rotate();
+                if !variants.contains(&template) {
+                    variants.push(template);
+                }
+                template = next;
+            }
+        }
+        variants
+    }
+
+    /// Whether the template's top left corner can be put on the cell.
+    pub fn matches_at(&self, puzzle: &Puzzle, r_idx: usize, c_idx: usize) -> bool {
+        self.width() > 0
+            && self.cells.iter().enumerate().all(|(dr, row)| {
+                row.iter().enumerate().all(|(dc, cell)| {
+                    let letter = puzzle.get(r_idx + dr, c_idx + dc);
+                    letter.is_some() && cell.is_none_or(|c| letter == Some(c))
+                })
+            })
+    }
+}
+
+const XMAS: &str = "XMAS";
+const X_MAS: &str = "M.S\n.A.\nM.S";
+
+/// The variants of the [`X_MAS`] template, built on first use.
+fn crossed_mas() -> &'static [Template] {
+    static VARIANTS: OnceLock<Vec<Template>> = OnceLock::new();
+    VARIANTS.get_or_init(|| Template::parse(X_MAS).variants())
 }
 
-const XMAS: [char; 4] = ['X', 'M', 'A', 'S'];
 impl Puzzle {
     pub fn columns(&self) -> usize {
-        if self.rows() > 0 {
-            self.data[0].len()
-        } else {
-            0
-        }
+        self.columns
     }
 
     pub fn rows(&self) -> usize {
-        self.data.len()
+        self.rows
     }
 
     pub fn get(&self, r_idx: usize, c_idx: usize) -> Option<char> {
         if r_idx < self.rows() && c_idx < self.columns() {
-            Some(self.data[r_idx][c_idx])
+            Some(char::from(self.data[r_idx * self.columns + c_idx]))
         } else {
             None
         }
     }
 
-    fn count_xmas(&self) -> usize {
-        let mut count = 0;
+    /// The grid with every letter not part of one of `matches` replaced by
+    /// `.`. With `colors` the letters are colored by the direction of the
+    /// first match they are part of.
+    pub fn render(&self, matches: &[Match], colors: bool) -> String {
+        let mut directions: HashMap<(usize, usize), Direction> = HashMap::new();
+        for m in matches {
+            for cell in m.cells() {
+                directions.entry(cell).or_insert(m.direction);
+            }
+        }
+
+        let mut out = String::with_capacity(self.rows * (self.columns + 1));
         for r_idx in 0..self.rows() {
             for c_idx in 0..self.columns() {
-                if self.get(r_idx, c_idx) == Some('X') {
-                    count += self.count_xmas_at(r_idx, c_idx);
+                match directions.get(&(r_idx, c_idx)) {
+                    Some(direction) if colors => {
+                        out.push_str(direction.color());
+                        out.push(self.get(r_idx, c_idx).unwrap_or('.'));
+                        out.push_str("\x1b[0m");
+                    }
+                    Some(_) => out.push(self.get(r_idx, c_idx).unwrap_or('.')),
+                    None => out.push('.'),
                 }
             }
+            out.push('\n');
         }
-        count
+        out
     }
 
-    fn count_crossed_mas(&self) -> usize {
+    /// Occurrences of `word` read left to right. Each row is compared with
+    /// one letter of the word at a time at a fixed offset, a loop without
+    /// branches the compiler can vectorize.
+    pub fn count_in_rows(&self, word: &str) -> usize {
+        let word = word.as_bytes();
+        if word.is_empty() || word.len() > self.columns {
+            return 0;
+        }
+        let starts = self.columns - word.len() + 1;
+        let mut hits = vec![0u8; starts];
         let mut count = 0;
+        for row in self.data.chunks_exact(self.columns) {
+            hits.fill(1);
+            for (offset, letter) in word.iter().enumerate() {
+                for (hit, cell) in hits.iter_mut().zip(&row[offset..offset + starts]) {
+                    *hit &= u8::from(cell == letter);
+                }
+            }
+            count += hits.iter().map(|hit| usize::from(*hit)).sum::<usize>();
+        }
+        count
+    }
+
+    /// All occurrences of the words of `search`, ordered by position, then
+    /// word, then direction.
+    pub fn find(&self, search: &WordSearch) -> Vec<Match> {
+        let mut matches = vec![];
+        for row in 0..self.rows() {
+            for column in 0..self.columns() {
+                for (word, text) in search.words.iter().enumerate() {
+                    for &direction in &search.directions {
+                        if self.is_word_at(row, column, direction.step(), text) {
+                            matches.push(Match {
+                                word,
+                                row,
+                                column,
+                                direction,
+                                len: text.chars().count(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        search.select(matches)
+    }
+
+    /// Same as [`Puzzle::find`], but reads every line of the grid in the
+    /// searched directions once with an automaton of all words instead of
+    /// trying each word at each cell.
+    pub fn find_batch(&self, search: &WordSearch) -> Vec<Match> {
+        let automaton = Automaton::new(&search.words);
+        let mut matches = vec![];
+        for &direction in &search.directions {
+            for line in self.lines(direction) {
+                let mut state = 0;
+                for (end, &(r_idx, c_idx)) in line.iter().enumerate() {
+                    let letter = self.data[r_idx * self.columns + c_idx];
+                    state = automaton.next(state, char::from(letter));
+                    for &(word, len) in &automaton.output[state] {
+                        let (row, column) = line[end + 1 - len];
+                        matches.push(Match {
+                            word,
+                            row,
+                            column,
+                            direction,
+                            len,
+                        });
+                    }
+                }
+            }
+        }
+
+        let rank: HashMap<Direction, usize> = search
+            .directions
+            .iter()
+            .enumerate()
+            .map(|(rank, direction)| (*direction, rank))
+            .collect();
+        matches.sort_by_key(|m| (m.row, m.column, m.word, rank[&m.direction]));
+        search.select(matches)
+    }
+
+    /// Cells of every line through the grid in `direction`, in reading order.
+    fn lines(&self, direction: Direction) -> Vec<Vec<(usize, usize)>> {
+        let (dr, dc) = direction.step();
+        let mut lines = vec![];
         for r_idx in 0..self.rows() {
             for c_idx in 0..self.columns() {
-                if self.is_crossed_mas_at(r_idx, c_idx) {
-                    count += 1;
+                let before = r_idx
+                    .checked_add_signed(-dr)
+                    .zip(c_idx.checked_add_signed(-dc))
+                    .and_then(|(r, c)| self.get(r, c));
+                if before.is_some() {
+                    continue;
+                }
+                let mut line = vec![];
+                let (mut r, mut c) = (r_idx, c_idx);
+                while self.get(r, c).is_some() {
+                    line.push((r, c));
+                    r = r.wrapping_add_signed(dr);
+                    c = c.wrapping_add_signed(dc);
                 }
+                lines.push(line);
             }
         }
-        count
+        lines
+    }
+
+    fn count_xmas(&self) -> usize {
+        self.find(&WordSearch::new(&[XMAS])).len()
+    }
+
+    fn count_crossed_mas(&self) -> usize {
+        self.count_templates(crossed_mas())
     }
     /*
 
 
     */
-    fn count_xmas_at(&self, r_idx: usize, c_idx: usize) -> usize {
+    /// Number of directions `XMAS` can be read in starting at the cell.
+    pub fn count_xmas_at(&self, r_idx: usize, c_idx: usize) -> usize {
         if self.get(r_idx, c_idx) != Some('X') {
             return 0;
         }
 
-        let mut count = 0;
-
-        let is_xmas_at =
-            |r_idx: usize, c_idx: usize, dir: (isize, isize)| self.is_xmas_at(r_idx, c_idx, dir);
-        for dir in [
-            (1, 0),
-            (0, 1),
-            (1, 1),
-            (0, -1),
-            (-1, 0),
-            (1, -1),
-            (-1, -1),
-            (-1, 1),
-            /*
-             */
-        ] {
-            if is_xmas_at(r_idx, c_idx, dir) {
-                count += 1;
-            }
-        }
+        Direction::ALL
+            .iter()
+            .filter(|dir| self.is_xmas_at(r_idx, c_idx, dir.step()))
+            .count()
+    }
 
-        count
+    pub fn is_xmas_at(&self, r_idx: usize, c_idx: usize, dir: (isize, isize)) -> bool {
+        self.is_word_at(r_idx, c_idx, dir, XMAS)
     }
 
-    fn is_xmas_at(&self, r_idx: usize, c_idx: usize, dir: (isize, isize)) -> bool {
-        if self.get(r_idx, c_idx) != Some('X') {
-            return false;
+    pub fn is_word_at(&self, r_idx: usize, c_idx: usize, dir: (isize, isize), word: &str) -> bool {
+        let mut letters = word.chars();
+        match letters.next() {
+            Some(first) if self.get(r_idx, c_idx) == Some(first) => {}
+            _ => return false,
         }
         let mut r_idx = r_idx;
         let mut c_idx = c_idx;
 
-        for c in &XMAS[1..] {
+        for c in letters {
             if let Some(new_r) = r_idx.checked_add_signed(dir.0) {
                 r_idx = new_r
             } else {
@@ -112,71 +520,83 @@ impl Puzzle {
             } else {
                 return false;
             }
-            if self.get(r_idx, c_idx) != Some(*c) {
+            if self.get(r_idx, c_idx) != Some(c) {
                 return false;
             }
         }
         true
     }
 
-    fn is_crossed_mas_at(&self, r_idx: usize, c_idx: usize) -> bool {
-        let mut lr = false;
-        let mut rl = false;
-        if self.get(r_idx, c_idx) != Some('A') {
-            return false;
-        }
-        if let Some(r_idx) = r_idx.checked_sub(1) {
-            if let Some(c_idx) = c_idx.checked_sub(1) {
-                if (self.get(r_idx, c_idx) == Some('M')
-                    && self.get(r_idx + 2, c_idx + 2) == Some('S'))
-                    || (self.get(r_idx, c_idx) == Some('S')
-                        && self.get(r_idx + 2, c_idx + 2) == Some('M'))
-                {
-                    lr = true;
-                }
-            }
+    /// Whether the cell is the `A` in the middle of an X-MAS.
+    pub fn is_crossed_mas_at(&self, r_idx: usize, c_idx: usize) -> bool {
+        match (r_idx.checked_sub(1), c_idx.checked_sub(1)) {
+            (Some(r_idx), Some(c_idx)) => crossed_mas()
+                .iter()
+                .any(|template| template.matches_at(self, r_idx, c_idx)),
+            _ => false,
         }
-        if let Some(r_idx) = r_idx.checked_sub(1) {
-            if let Some(c_idx) = c_idx.checked_sub(1) {
-                if (self.get(r_idx, c_idx + 2) == Some('M')
-                    && self.get(r_idx + 2, c_idx) == Some('S'))
-                    || (self.get(r_idx, c_idx + 2) == Some('S')
-                        && self.get(r_idx + 2, c_idx) == Some('M'))
-                {
-                    rl = true;
-                }
+    }
+
+    /// Number of placements of any of `templates` on the puzzle.
+    pub fn count_templates(&self, templates: &[Template]) -> usize {
+        let mut count = 0;
+        for r_idx in 0..self.rows() {
+            for c_idx in 0..self.columns() {
+                count += templates
+                    .iter()
+                    .filter(|template| template.matches_at(self, r_idx, c_idx))
+                    .count();
             }
         }
-        lr && rl
+        count
     }
 }
 
-fn parse_input(var_name: &str) -> Result<Puzzle, Error> {
-    let input_parsed: Vec<Vec<char>> = var_name
-        .lines()
-        .map(|s| s.to_string().chars().collect::<Vec<char>>())
-        .collect();
-    Ok(Puzzle { data: input_parsed })
-}
-
-#[derive(Debug)]
-pub enum Error {
-    InputError(io::Error),
-}
-
-impl From<io::Error> for Error {
-    fn from(value: io::Error) -> Self {
-        Error::InputError(value)
+pub fn parse_input(var_name: &str) -> Result<Puzzle, Error> {
+    let mut data = Vec::with_capacity(var_name.len());
+    let mut columns = 0;
+    let mut rows = 0;
+    let lines: Vec<&str> = var_name.lines().collect();
+    // trailing blank lines are not rows
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |last| last + 1);
+    for (line_idx, line) in lines[..end].iter().enumerate() {
+        if let Some(column) = line.find(|c: char| !c.is_ascii()) {
+            let c = line[column..].chars().next().unwrap_or_default();
+            return Err(Error::parsing(
+                line_idx + 1,
+                column + 1,
+                ParseErrorKind::UnexpectedChar(c),
+            ));
+        }
+        if line_idx == 0 {
+            columns = line.len();
+        } else if line.len() != columns {
+            return Err(Error::parsing(
+                line_idx + 1,
+                columns.min(line.len()) + 1,
+                ParseErrorKind::ColumnsExpected {
+                    expected: columns,
+                    found: line.len(),
+                },
+            ));
+        }
+        data.extend_from_slice(line.as_bytes());
+        rows += 1;
     }
+    Ok(Puzzle {
+        data,
+        rows,
+        columns,
+    })
 }
 
-
 #[cfg(test)]
 mod tests {
 
-    use std::fs::File;
-
-    use io::Read;
+    use std::{fs::File, io::Read};
 
     use super::*;
 
@@ -207,6 +627,123 @@ mod tests {
         assert_eq!(2, input.count_xmas_at(4, 6));
     }
 
+    #[test]
+    fn find_words() {
+        let puzzle = parse_input("XMASAMX\n.......\nS......").unwrap();
+
+        let mut search = WordSearch::new(&["XMAS", "SAMX"]);
+        search.directions = vec![Direction::Right, Direction::Up];
+        assert_eq!(
+            vec![
+                Match {
+                    word: 0,
+                    row: 0,
+                    column: 0,
+                    direction: Direction::Right,
+                    len: 4
+                },
+                Match {
+                    word: 1,
+                    row: 0,
+                    column: 3,
+                    direction: Direction::Right,
+                    len: 4
+                },
+            ],
+            puzzle.find(&search)
+        );
+
+        search.overlap = Overlap::Disjoint;
+        assert_eq!(1, puzzle.find(&search).len());
+
+        // reading left finds both words a second time
+        search.directions = Direction::ALL.to_vec();
+        search.overlap = Overlap::Allowed;
+        assert_eq!(4, puzzle.find(&search).len());
+    }
+
+    #[test]
+    fn find_batch_matches_find() {
+        let input = parse_from_file("input/2024/day4.txt").unwrap();
+        let mut search = WordSearch::new(&["XMAS", "MAS", "AS", "SAMX", "XX", "MASAM", ""]);
+
+        let matches = input.find_batch(&search);
+        assert_eq!(input.find(&search), matches);
+        assert_eq!(input.count_xmas(), search.counts(&matches)[0]);
+
+        search.directions = vec![Direction::UpLeft, Direction::Right];
+        search.overlap = Overlap::Disjoint;
+        assert_eq!(input.find(&search), input.find_batch(&search));
+    }
+
+    #[test]
+    fn match_cells() {
+        let found = Match {
+            word: 0,
+            row: 3,
+            column: 3,
+            direction: Direction::UpLeft,
+            len: 4,
+        };
+
+        assert_eq!(
+            vec![(3, 3), (2, 2), (1, 1), (0, 0)],
+            found.cells().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn parse_ragged_input() {
+        assert_eq!(
+            "line 3, column 3: expected 3 columns, found 2",
+            parse_input("XMA\nSAM\nXM\n").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "line 2, column 2: unexpected character 'Ä'",
+            parse_input("XMA\nSÄM").unwrap_err().to_string()
+        );
+        assert_eq!(0, parse_input("").unwrap().columns());
+        assert_eq!(2, parse_input("XMAS\nXMAS\n\n").unwrap().rows());
+        assert_eq!(
+            "line 2, column 1: expected 4 columns, found 0",
+            parse_input("XMAS\n\nXMAS").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn count_in_rows_matches_find() {
+        let input = parse_from_file("input/2024/day4.txt").unwrap();
+        let mut search = WordSearch::new(&["XMAS", "SAMX", "A"]);
+        search.directions = vec![Direction::Right];
+        let counts = search.counts(&input.find(&search));
+
+        assert_eq!(counts[0], input.count_in_rows("XMAS"));
+        assert_eq!(counts[1], input.count_in_rows("SAMX"));
+        assert_eq!(counts[2], input.count_in_rows("A"));
+        assert_eq!(0, input.count_in_rows(&"X".repeat(input.columns() + 1)));
+    }
+
+    #[test]
+    fn render_golden() {
+        let input = parse_from_file("test_data/day4.txt").unwrap();
+        let matches = input.find(&WordSearch::new(&[XMAS]));
+
+        let golden = std::fs::read_to_string("test_data/day4_xmas.txt").unwrap();
+        assert_eq!(golden, input.render(&matches, false));
+    }
+
+    #[test]
+    fn render_colors() {
+        let input = parse_input("XMAS\n.S..").unwrap();
+        let mut search = WordSearch::new(&[XMAS, "SA"]);
+        search.directions = vec![Direction::Right, Direction::UpRight];
+
+        assert_eq!(
+            "\x1b[32mX\x1b[0m\x1b[32mM\x1b[0m\x1b[32mA\x1b[0m\x1b[32mS\x1b[0m\n.\x1b[92mS\x1b[0m..\n",
+            input.render(&input.find(&search), true)
+        );
+    }
+
     #[test]
     fn count_xmas_input_test() {
         let input = parse_from_file("test_data/day4.txt").unwrap();
@@ -227,6 +764,33 @@ mod tests {
         assert!(input.is_crossed_mas_at(1, 2));
     }
 
+    #[test]
+    fn template_variants() {
+        let template = Template::parse("AB\nC");
+
+        assert_eq!(Template::parse("CA\n.B"), template.rotate());
+        assert_eq!(Template::parse("BA\n.C"), template.reflect());
+        assert_eq!(8, template.variants().len());
+        assert_eq!(4, Template::parse(X_MAS).variants().len());
+        assert_eq!(2, Template::parse("A.\n.A").variants().len());
+    }
+
+    #[test]
+    fn count_templates_input_test() {
+        let input = parse_from_file("test_data/day4.txt").unwrap();
+
+        // horizontal and vertical XMAS are the rotations of one template
+        let straight = Template::parse(XMAS).variants();
+        assert_eq!(4, straight.len());
+        let diagonal = Template::parse("X...\n.M..\n..A.\n...S").variants();
+        assert_eq!(18, input.count_templates(&[straight, diagonal].concat()));
+
+        let small = parse_input("XM\nAS").unwrap();
+        assert_eq!(4, small.count_templates(&[Template::parse(".")]));
+        assert_eq!(0, small.count_templates(&[Template::parse("")]));
+        assert_eq!(0, small.count_templates(&[Template::parse("\n")]));
+    }
+
     #[test]
     fn count_mas_input_test() {
         let input = parse_from_file("test_data/day4.txt").unwrap();
@@ -1,90 +1,396 @@
 use std::{
-    cmp::Ordering,
-    collections::HashMap,
-    io::{self},
-    num::ParseIntError,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Display},
 };
 
 use aoc_runner_derive::aoc;
 
+use crate::error::{Error, ParseErrorKind};
+
 #[aoc(day5, part1)]
-pub fn part1(input: &str) -> i32 {
-    let (rules, updates) = parse_input(input).unwrap();
+pub fn part1(input: &str) -> Result<i32, Error> {
+    let (rules, updates) = parse_input(input)?;
     let ord = CustomOrd::new(rules);
 
-    updates
+    Ok(updates
         .iter()
-        .filter(|u| ord.check(u))
+        .filter(|u| ord.is_ordered(u))
         .filter_map(|u| {
             let middle = u.len() / 2;
             u.get(middle)
         })
         .copied()
-        .sum()
+        .sum())
 }
 
 #[aoc(day5, part2)]
-pub fn part2(input: &str) -> i32 {
-    let (rules, updates) = parse_input(input).unwrap();
+pub fn part2(input: &str) -> Result<i32, Error> {
+    let (rules, updates) = parse_input(input)?;
     let ord = CustomOrd::new(rules);
 
     let mut sum = 0;
-    for mut u in updates.into_iter().filter(|u| !ord.check(u)) {
-        u.sort_by(&ord);
+    for mut u in updates.into_iter().filter(|u| !ord.is_ordered(u)) {
+        u.sort_by(&ord)?;
         let middle = u.len() / 2;
         sum += u.get(middle).unwrap();
     }
-    sum
+    Ok(sum)
+}
+
+#[aoc(day5, part2, explain)]
+pub fn part2_explain(input: &str) -> Result<String, Error> {
+    let (rules, updates) = parse_input(input)?;
+    let ord = CustomOrd::new(rules);
+
+    let mut report = String::new();
+    for (idx, update) in updates.iter().enumerate() {
+        let violations = ord.check(update);
+        if violations.is_empty() {
+            continue;
+        }
+        match ord.moves_to_fix(update) {
+            Ok(moves) => report.push_str(&format!("\nupdate {} needs {} moves:", idx + 1, moves)),
+            Err(e) => report.push_str(&format!("\nupdate {} can't be fixed, {}:", idx + 1, e)),
+        }
+        for violation in violations {
+            report.push_str(&format!("\n  {}", violation));
+        }
+    }
+    Ok(report)
+}
+
+#[aoc(day5, part1, validate)]
+pub fn part1_validate(input: &str) -> Result<String, Error> {
+    let (rules, updates) = parse_input(input)?;
+    Ok(format!("\n{}", validate(&rules, &updates)))
 }
 
+/// Pages above this are looked up in a hash map instead of a bit matrix.
+const MAX_DENSE_PAGE: i32 = 1023;
+
 pub struct CustomOrd {
-    rules: HashMap<i32, HashMap<i32, OrdRule>>,
+    rules: Rules,
+}
+
+/// The pages each page has to be printed before.
+enum Rules {
+    /// Bit `page2` of row `page1` is set for each rule, rows are `words` long.
+    Dense {
+        words: usize,
+        bits: Vec<u64>,
+    },
+    Hashed(HashMap<i32, HashMap<i32, OrdRule>>),
 }
 
 impl CustomOrd {
-    fn new(rules: Vec<OrdRule>) -> Self {
+    /// Uses a bit matrix for page numbers from 0 to [`MAX_DENSE_PAGE`] and
+    /// falls back to hash maps otherwise.
+    pub fn new(rules: Vec<OrdRule>) -> Self {
+        let pages = rules.iter().flat_map(|rule| [rule.page1, rule.page2]);
+        match (pages.clone().min(), pages.max()) {
+            (Some(min), Some(max)) if min >= 0 && max <= MAX_DENSE_PAGE => {
+                let words = (max as usize + 1).div_ceil(64);
+                let mut bits = vec![0u64; words * (max as usize + 1)];
+                for rule in rules {
+                    let (page1, page2) = (rule.page1 as usize, rule.page2 as usize);
+                    bits[page1 * words + page2 / 64] |= 1 << (page2 % 64);
+                }
+                CustomOrd {
+                    rules: Rules::Dense { words, bits },
+                }
+            }
+            _ => CustomOrd::hashed(rules),
+        }
+    }
+
+    /// Keeps the rules in hash maps regardless of the page numbers.
+    pub fn hashed(rules: Vec<OrdRule>) -> Self {
         let initial: HashMap<i32, HashMap<i32, OrdRule>> = HashMap::new();
         let rules = rules.into_iter().fold(initial, |mut m, rule| {
-            m.entry(rule.page1)
-                .or_default()
-                .insert(rule.page2, rule);
+            m.entry(rule.page1).or_default().insert(rule.page2, rule);
             m
         });
-        CustomOrd { rules }
+        CustomOrd {
+            rules: Rules::Hashed(rules),
+        }
     }
 
-    fn cmp(&self, o1: &i32, o2: &i32) -> Ordering {
-        if o1 == o2 {
-            return Ordering::Equal;
-        }
-        if let Some(rules_o1) = self.rules.get(o1) {
-            if rules_o1.contains_key(o2) {
-                return Ordering::Less;
+    /// Whether no rule puts a page before one printed earlier, the same as
+    /// [`CustomOrd::check`] finding nothing. A page printed twice breaks a
+    /// rule putting it before itself. Remembers the pages seen so far, so
+    /// each page is looked up once instead of once per pair of pages.
+    pub fn is_ordered(&self, update: &Update) -> bool {
+        match &self.rules {
+            Rules::Dense { words, bits } => {
+                let mut seen = vec![0u64; *words];
+                for page in &update.pages {
+                    let Some(row) = self.dense_row(*page) else {
+                        continue;
+                    };
+                    let successors = &bits[row * words..(row + 1) * words];
+                    if successors.iter().zip(&seen).any(|(s, seen)| s & seen != 0) {
+                        return false;
+                    }
+                    seen[row / 64] |= 1 << (row % 64);
+                }
+                true
             }
+            Rules::Hashed(rules) => {
+                let mut position: HashMap<i32, usize> = HashMap::new();
+                for (idx, page) in update.pages.iter().enumerate() {
+                    position.entry(*page).or_insert(idx);
+                }
+                update.pages.iter().enumerate().all(|(idx, page)| {
+                    rules.get(page).is_none_or(|successors| {
+                        successors
+                            .keys()
+                            .all(|s| position.get(s).is_none_or(|p| *p >= idx))
+                    })
+                })
+            }
+        }
+    }
+
+    /// Row of `page` in the bit matrix, if it has one.
+    fn dense_row(&self, page: i32) -> Option<usize> {
+        match &self.rules {
+            Rules::Dense { words, bits } => usize::try_from(page)
+                .ok()
+                .filter(|row| (row + 1) * words <= bits.len()),
+            Rules::Hashed(_) => None,
         }
-        if let Some(rules_o2) = self.rules.get(o2) {
-            if rules_o2.contains_key(o1) {
-                return Ordering::Greater;
+    }
+
+    fn before(&self, page1: i32, page2: i32) -> bool {
+        match &self.rules {
+            Rules::Dense { words, bits } => match (self.dense_row(page1), self.dense_row(page2)) {
+                (Some(row), Some(column)) => {
+                    bits[row * words + column / 64] & (1 << (column % 64)) != 0
+                }
+                _ => false,
+            },
+            Rules::Hashed(rules) => rules
+                .get(&page1)
+                .is_some_and(|rules| rules.contains_key(&page2)),
+        }
+    }
+
+    /// Pairs of pages printed in the order a rule forbids, empty if the
+    /// update is in order. A pair breaks the rule putting the later page
+    /// first even if another rule allows the order it is printed in.
+    pub fn check(&self, update: &Update) -> Vec<Violation> {
+        let mut violations = vec![];
+        for (first, page) in update.pages.iter().enumerate() {
+            for (offset, later) in update.pages[first + 1..].iter().enumerate() {
+                if self.before(*later, *page) {
+                    violations.push(Violation {
+                        first,
+                        second: first + 1 + offset,
+                        rule: OrdRule {
+                            page1: *later,
+                            page2: *page,
+                        },
+                    });
+                }
             }
         }
-        panic!("not comparable");
+        violations
     }
 
-    pub fn check(&self, update: &Update) -> bool {
-        let mut last_page: Option<i32> = None;
+    /// Least number of pages that have to be moved to put the update in
+    /// order: all pages but a largest set of them no two of which are
+    /// printed in the order the rules forbid, directly or through other pages
+    /// of the update. Fails only if those rules form a cycle.
+    pub fn moves_to_fix(&self, update: &Update) -> Result<usize, Error> {
+        let mut unique: Vec<i32> = vec![];
+        let mut index: HashMap<i32, usize> = HashMap::new();
         for page in &update.pages {
-            if let Some(last_page) = last_page {
-                if self.cmp(&last_page, page) != Ordering::Less {
-                    return false;
+            index.entry(*page).or_insert_with(|| {
+                unique.push(*page);
+                unique.len() - 1
+            });
+        }
+
+        // reach[a][b]: unique[a] has to be printed before unique[b]
+        let mut reach: Vec<Vec<bool>> = unique
+            .iter()
+            .map(|a| unique.iter().map(|b| self.before(*a, *b)).collect())
+            .collect();
+        for k in 0..unique.len() {
+            let via = reach[k].clone();
+            for row in reach.iter_mut().filter(|row| row[k]) {
+                row.iter_mut().zip(&via).for_each(|(r, v)| *r |= v);
+            }
+        }
+
+        let positions: Vec<usize> = update.pages.iter().map(|page| index[page]).collect();
+        for (a, page) in unique.iter().enumerate() {
+            if (0..unique.len()).any(|b| a != b && reach[a][b] && reach[b][a]) {
+                return Err(Error::RuleCycle(
+                    self.restricted_cycle(&unique).unwrap_or_default(),
+                ));
+            }
+            // a single copy of a page can't break a rule putting it before
+            // itself
+            if reach[a][a] && positions.iter().filter(|p| **p == a).count() > 1 {
+                return Err(Error::RuleCycle(vec![*page]));
+            }
+        }
+
+        // positions printed in the order the rules forbid are a partial
+        // order, the pages that stay are a largest antichain, so by
+        // Dilworth's theorem the moves are a maximum matching between the
+        // earlier and the later position of the forbidden pairs
+        let forbidden = |i: usize, j: usize| i < j && reach[positions[j]][positions[i]];
+        let mut matched: Vec<Option<usize>> = vec![None; positions.len()];
+        let mut moves = 0;
+        for i in 0..positions.len() {
+            let mut visited = vec![false; positions.len()];
+            if augment(i, &forbidden, &mut matched, &mut visited) {
+                moves += 1;
+            }
+        }
+        Ok(moves)
+    }
+
+    /// Orders `pages` by a topological sort of the rules between them, pages
+    /// not related by a rule directly may be ordered through other pages of
+    /// the list. Fails if those rules form a cycle or don't determine a
+    /// single order.
+    fn topological_sort(&self, pages: &[i32]) -> Result<Vec<i32>, Error> {
+        let mut unique: Vec<i32> = vec![];
+        let mut copies: HashMap<i32, usize> = HashMap::new();
+        for page in pages {
+            *copies.entry(*page).or_insert_with(|| {
+                unique.push(*page);
+                0
+            }) += 1;
+        }
+
+        let mut incoming: HashMap<i32, usize> = unique
+            .iter()
+            .map(|page| {
+                let count = unique.iter().filter(|p| self.before(**p, *page)).count();
+                (*page, count)
+            })
+            .collect();
+
+        let mut sorted = Vec::with_capacity(pages.len());
+        let mut placed: HashSet<i32> = HashSet::new();
+        while placed.len() < unique.len() {
+            let mut ready = unique
+                .iter()
+                .filter(|page| !placed.contains(page) && incoming[page] == 0);
+            let page = match (ready.next(), ready.next()) {
+                (Some(page), None) => *page,
+                (Some(page1), Some(page2)) => {
+                    return Err(Error::AmbiguousOrder(*page1, *page2));
+                }
+                (None, _) => return Err(Error::RuleCycle(self.find_cycle(&unique, &placed))),
+            };
+
+            placed.insert(page);
+            sorted.extend(std::iter::repeat_n(page, copies[&page]));
+            for next in unique.iter().filter(|next| self.before(page, **next)) {
+                if let Some(count) = incoming.get_mut(next) {
+                    *count -= 1;
                 }
             }
-            last_page = Some(*page);
         }
-        true
+        Ok(sorted)
+    }
+
+    /// A cycle of rules between `pages`, if any.
+    fn restricted_cycle(&self, pages: &[i32]) -> Option<Vec<i32>> {
+        let mut placed: HashSet<i32> = HashSet::new();
+        loop {
+            let ready = pages.iter().find(|page| {
+                !placed.contains(page)
+                    && pages
+                        .iter()
+                        .all(|p| placed.contains(p) || !self.before(*p, **page))
+            });
+            match ready {
+                Some(page) => {
+                    placed.insert(*page);
+                }
+                None if pages.iter().all(|page| placed.contains(page)) => return None,
+                None => return Some(self.find_cycle(pages, &placed)),
+            }
+        }
+    }
+
+    /// A cycle among the pages not `placed` yet, each of which has an
+    /// unplaced predecessor.
+    fn find_cycle(&self, pages: &[i32], placed: &HashSet<i32>) -> Vec<i32> {
+        let remaining: Vec<i32> = pages
+            .iter()
+            .filter(|page| !placed.contains(page))
+            .copied()
+            .collect();
+        let mut path: Vec<i32> = vec![remaining[0]];
+        loop {
+            let current = path[path.len() - 1];
+            let previous = remaining
+                .iter()
+                .find(|page| self.before(**page, current))
+                .copied()
+                .unwrap_or(current);
+            if let Some(start) = path.iter().position(|page| *page == previous) {
+                let mut cycle = path.split_off(start);
+                cycle.reverse();
+                return cycle;
+            }
+            path.push(previous);
+        }
+    }
+}
+
+/// Matches `i` to a `j` with `edge(i, j)` that is free or whose match can
+/// be moved on, along an augmenting path through the `visited` ones.
+fn augment(
+    i: usize,
+    edge: &impl Fn(usize, usize) -> bool,
+    matched: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for j in 0..matched.len() {
+        if visited[j] || !edge(i, j) {
+            continue;
+        }
+        visited[j] = true;
+        if matched[j].is_none_or(|k| augment(k, edge, matched, visited)) {
+            matched[j] = Some(i);
+            return true;
+        }
+    }
+    false
+}
+
+/// Pages at positions `first` < `second` of an update printed in the order
+/// `rule` forbids.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub first: usize,
+    pub second: usize,
+    pub rule: OrdRule,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (position {}) printed before {} (position {}) breaks {}",
+            self.rule.page2,
+            self.first + 1,
+            self.rule.page1,
+            self.second + 1,
+            self.rule
+        )
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct OrdRule {
     page1: i32,
     page2: i32,
@@ -102,29 +408,32 @@ impl PartialOrd for OrdRule {
     }
 }
 
+impl Display for OrdRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}|{}", self.page1, self.page2)
+    }
+}
+
 impl OrdRule {
+    /// Parses `page1|page2`.
     pub fn parse(line: &str) -> Result<OrdRule, Error> {
-        let mut page1 = 0;
-
-        let mut candidate = String::new();
-        for c in line.chars() {
-            if c.is_numeric() {
-                candidate.push(c);
-                continue;
-            } else if c == '|' {
-                page1 = candidate.parse::<i32>()?;
-                candidate.clear();
-                continue;
+        let pages = parse_pages(line, '|', Some(ParseErrorKind::IncompleteOrderRule))?;
+        match pages[..] {
+            [page1, page2] => Ok(OrdRule { page1, page2 }),
+            [_] => Err(Error::parsing(
+                1,
+                line.len() + 1,
+                ParseErrorKind::IncompleteOrderRule,
+            )),
+            _ => {
+                let (second, _) = line.match_indices('|').nth(1).unwrap_or_default();
+                Err(Error::parsing(
+                    1,
+                    second + 1,
+                    ParseErrorKind::UnexpectedChar('|'),
+                ))
             }
         }
-        if !candidate.is_empty() {
-            Ok(OrdRule {
-                page1,
-                page2: candidate.parse::<i32>()?,
-            })
-        } else {
-            Err(Error::InCompleteOrderRule)
-        }
     }
 }
 #[derive(Debug, PartialEq)]
@@ -132,23 +441,11 @@ pub struct Update {
     pages: Vec<i32>,
 }
 impl Update {
+    /// Parses comma separated pages.
     pub fn parse(line: &str) -> Result<Self, Error> {
-        let mut pages: Vec<i32> = Vec::new();
-        let mut candidate = String::new();
-        for c in line.chars() {
-            if c.is_numeric() {
-                candidate.push(c);
-                continue;
-            } else if c == ',' {
-                pages.push(candidate.parse::<i32>()?);
-                candidate.clear();
-                continue;
-            }
-        }
-        if !candidate.is_empty() {
-            pages.push(candidate.parse::<i32>()?);
-        }
-        Ok(Update { pages })
+        Ok(Update {
+            pages: parse_pages(line, ',', None)?,
+        })
     }
 
     fn len(&self) -> usize {
@@ -159,45 +456,259 @@ impl Update {
         self.pages.get(index)
     }
 
-    fn sort_by(&mut self, ord: &CustomOrd) {
-        self.pages.sort_by(|a, b| ord.cmp(a, b));
+    fn sort_by(&mut self, ord: &CustomOrd) -> Result<(), Error> {
+        self.pages = ord.topological_sort(&self.pages)?;
+        Ok(())
     }
 }
+/// Parses the page numbers between the `separator`s of `line`, which may
+/// contain nothing but digits and separators. A missing page is reported as
+/// `missing`, or as an invalid number if that is `None`.
+fn parse_pages(
+    line: &str,
+    separator: char,
+    missing: Option<ParseErrorKind>,
+) -> Result<Vec<i32>, Error> {
+    let mut pages = vec![];
+    // all characters before an unexpected one are ascii, so byte offsets
+    // are columns
+    let mut start = 0;
+    for (idx, c) in line.char_indices().chain([(line.len(), separator)]) {
+        if c == separator {
+            let candidate = &line[start..idx];
+            match &missing {
+                Some(kind) if candidate.is_empty() => {
+                    return Err(Error::parsing(1, idx + 1, kind.clone()));
+                }
+                _ => pages.push(parse_page(candidate, idx + 1)?),
+            }
+            start = idx + c.len_utf8();
+        } else if !c.is_ascii_digit() {
+            return Err(Error::parsing(
+                1,
+                idx + 1,
+                ParseErrorKind::UnexpectedChar(c),
+            ));
+        }
+    }
+    Ok(pages)
+}
+
+/// Parses the page number collected in `candidate`, `end` being the column
+/// right after it.
+fn parse_page(candidate: &str, end: usize) -> Result<i32, Error> {
+    candidate.parse::<i32>().map_err(|e| {
+        Error::parsing(
+            1,
+            end - candidate.chars().count(),
+            ParseErrorKind::Int {
+                token: candidate.to_string(),
+                source: e,
+            },
+        )
+    })
+}
+
+/// Problems of a list of ordering rules, see [`validate`].
 #[derive(Debug, PartialEq)]
-pub enum Error {
-    IO(std::io::ErrorKind),
-    ParseInt(core::num::IntErrorKind),
-    InCompleteOrderRule,
+pub struct Validation {
+    /// Rules listed more than once and how often they are.
+    pub duplicates: Vec<(OrdRule, usize)>,
+    /// Pages `(a, b)` with both `a|b` and `b|a`, `a < b`.
+    pub contradictions: Vec<(i32, i32)>,
+    /// Strongly connected components of the rule graph that contain a cycle,
+    /// pages in ascending order.
+    pub components: Vec<Vec<i32>>,
+    /// For each update a cycle of the rules between its pages, `None` if
+    /// they are acyclic.
+    pub update_cycles: Vec<Option<Vec<i32>>>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.duplicates.is_empty()
+            && self.contradictions.is_empty()
+            && self.update_cycles.iter().all(Option::is_none)
+    }
 }
 
-impl From<io::Error> for Error {
-    fn from(value: io::Error) -> Self {
-        Error::IO(value.kind())
+impl Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate rules:")?;
+        if self.duplicates.is_empty() {
+            write!(f, " none")?;
+        }
+        for (rule, count) in &self.duplicates {
+            write!(f, " {} (x{})", rule, count)?;
+        }
+
+        write!(f, "\ncontradicting rules:")?;
+        if self.contradictions.is_empty() {
+            write!(f, " none")?;
+        }
+        for (a, b) in &self.contradictions {
+            write!(f, " {}|{} and {}|{}", a, b, b, a)?;
+        }
+
+        write!(f, "\ncyclic components:")?;
+        if self.components.is_empty() {
+            write!(f, " none")?;
+        }
+        for component in &self.components {
+            write!(f, " {} pages {:?}", component.len(), component)?;
+        }
+
+        let cyclic = self.update_cycles.iter().filter(|c| c.is_some()).count();
+        writeln!(
+            f,
+            "\nupdates with cyclic rules: {} of {}",
+            cyclic,
+            self.update_cycles.len()
+        )?;
+        for (idx, cycle) in self.update_cycles.iter().enumerate() {
+            if let Some(cycle) = cycle {
+                writeln!(
+                    f,
+                    "  update {}: {}",
+                    idx + 1,
+                    Error::RuleCycle(cycle.clone())
+                )?;
+            }
+        }
+        Ok(())
     }
 }
 
-impl From<ParseIntError> for Error {
-    fn from(value: ParseIntError) -> Self {
-        Error::ParseInt(value.kind().clone())
+/// Checks `rules` for duplicates, contradictions and cycles, globally and
+/// restricted to the pages of each of `updates`.
+pub fn validate(rules: &[OrdRule], updates: &[Update]) -> Validation {
+    let mut counts: BTreeMap<(i32, i32), usize> = BTreeMap::new();
+    for rule in rules {
+        *counts.entry((rule.page1, rule.page2)).or_default() += 1;
+    }
+    let unique: Vec<OrdRule> = counts
+        .keys()
+        .map(|(page1, page2)| OrdRule {
+            page1: *page1,
+            page2: *page2,
+        })
+        .collect();
+    let duplicates = unique
+        .iter()
+        .zip(counts.values())
+        .filter(|(_, count)| **count > 1)
+        .map(|(rule, count)| (*rule, *count))
+        .collect();
+    let contradictions = counts
+        .keys()
+        .filter(|(page1, page2)| page1 < page2 && counts.contains_key(&(*page2, *page1)))
+        .copied()
+        .collect();
+
+    let ord = CustomOrd::new(unique.clone());
+    let components = strongly_connected(&unique)
+        .into_iter()
+        .filter(|component| component.len() > 1 || ord.before(component[0], component[0]))
+        .collect();
+    let update_cycles = updates
+        .iter()
+        .map(|update| ord.restricted_cycle(&update.pages))
+        .collect();
+
+    Validation {
+        duplicates,
+        contradictions,
+        components,
+        update_cycles,
+    }
+}
+
+/// Strongly connected components of the rule graph (Kosaraju), ordered by
+/// their smallest page.
+fn strongly_connected(rules: &[OrdRule]) -> Vec<Vec<i32>> {
+    let mut successors: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    let mut predecessors: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for rule in rules {
+        successors.entry(rule.page1).or_default().push(rule.page2);
+        successors.entry(rule.page2).or_default();
+        predecessors.entry(rule.page2).or_default().push(rule.page1);
+        predecessors.entry(rule.page1).or_default();
+    }
+
+    // pages by the time their depth first search along the rules finished
+    let mut finished: Vec<i32> = vec![];
+    let mut visited: HashSet<i32> = HashSet::new();
+    for start in successors.keys() {
+        if !visited.insert(*start) {
+            continue;
+        }
+        let mut stack = vec![(*start, 0)];
+        while let Some((page, next)) = stack.pop() {
+            match successors[&page].get(next) {
+                Some(successor) => {
+                    stack.push((page, next + 1));
+                    if visited.insert(*successor) {
+                        stack.push((*successor, 0));
+                    }
+                }
+                None => finished.push(page),
+            }
+        }
+    }
+
+    let mut components: Vec<Vec<i32>> = vec![];
+    let mut assigned: HashSet<i32> = HashSet::new();
+    for start in finished.iter().rev() {
+        if !assigned.insert(*start) {
+            continue;
+        }
+        let mut component = vec![];
+        let mut stack = vec![*start];
+        while let Some(page) = stack.pop() {
+            component.push(page);
+            for predecessor in &predecessors[&page] {
+                if assigned.insert(*predecessor) {
+                    stack.push(*predecessor);
+                }
+            }
+        }
+        component.sort_unstable();
+        components.push(component);
     }
+    components.sort_unstable();
+    components
 }
 
+/// Parses the ordering rules, one per line, then after an empty line the
+/// updates. Updates need a middle page, blank lines may only trail them.
 pub fn parse_input(input: &str) -> Result<(Vec<OrdRule>, Vec<Update>), Error> {
     let mut rules_part = true;
 
     let mut rules = Vec::<OrdRule>::new();
     let mut updates: Vec<Update> = Vec::new();
 
-    for line in input.lines() {
-        if line.trim().is_empty() {
+    let lines: Vec<&str> = input.lines().collect();
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |last| last + 1);
+    for (line_idx, line) in lines[..end].iter().enumerate() {
+        if rules_part && line.is_empty() {
             rules_part = false;
-            continue;
         } else if rules_part {
-            rules.push(OrdRule::parse(line)?);
-            continue;
+            rules.push(OrdRule::parse(line).map_err(|e| e.on_line(line_idx + 1))?);
+        } else if line.is_empty() {
+            return Err(Error::parsing(line_idx + 1, 1, ParseErrorKind::EmptyUpdate));
         } else {
-            updates.push(Update::parse(line)?);
-            continue;
+            let update = Update::parse(line).map_err(|e| e.on_line(line_idx + 1))?;
+            if update.len() % 2 == 0 {
+                return Err(Error::parsing(
+                    line_idx + 1,
+                    1,
+                    ParseErrorKind::EvenUpdate(update.len()),
+                ));
+            }
+            updates.push(update);
         }
     }
     Ok((rules, updates))
@@ -208,6 +719,7 @@ mod tests {
     use std::{fs::File, io::Read};
 
     use super::*;
+    use crate::test_rng::Rng;
 
     #[test]
     fn parse_update_simple() {
@@ -238,15 +750,371 @@ mod tests {
         }));
         let ord = CustomOrd::new(rules);
         let first = updates.first().unwrap();
-        assert!(ord.check(first));
-        assert!(!ord.check(updates.get(3).unwrap()));
+        assert!(ord.check(first).is_empty());
+        assert!(!ord.check(updates.get(3).unwrap()).is_empty());
     }
 
     #[test]
     fn part1_test_input() {
         let input = load("test_data/day5.txt").unwrap();
 
-        assert_eq!(143, part1(input.as_str()));
+        assert_eq!(Ok(143), part1(input.as_str()));
+    }
+
+    #[test]
+    fn part2_test_input() {
+        let input = load("test_data/day5.txt").unwrap();
+
+        assert_eq!(Ok(123), part2(input.as_str()));
+    }
+
+    #[test]
+    fn sort_through_transitive_rules() {
+        // no rule orders 1 and 3 directly
+        let (rules, updates) = parse_input("1|2\n2|3\n\n3,1,2\n2,2,1").unwrap();
+        let ord = CustomOrd::new(rules);
+
+        let mut sorted = vec![];
+        for mut update in updates {
+            assert!(!ord.check(&update).is_empty());
+            update.sort_by(&ord).unwrap();
+            assert!(ord.check(&update).is_empty());
+            sorted.push(update.pages);
+        }
+        assert_eq!(vec![vec![1, 2, 3], vec![1, 2, 2]], sorted);
+    }
+
+    #[test]
+    fn explain_test_input() {
+        let (rules, updates) = parse("test_data/day5.txt").unwrap();
+        let ord = CustomOrd::new(rules);
+
+        // 97,13,75,29,47
+        let update = &updates[5];
+        assert_eq!(
+            vec![
+                "13 (position 2) printed before 75 (position 3) breaks 75|13",
+                "13 (position 2) printed before 29 (position 4) breaks 29|13",
+                "13 (position 2) printed before 47 (position 5) breaks 47|13",
+                "29 (position 4) printed before 47 (position 5) breaks 47|29",
+            ],
+            ord.check(update)
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+        );
+        // 97,75,47,29,13
+        assert_eq!(Ok(2), ord.moves_to_fix(update));
+
+        let moves: Result<Vec<usize>, Error> =
+            updates.iter().map(|u| ord.moves_to_fix(u)).collect();
+        assert_eq!(Ok(vec![0, 0, 0, 1, 1, 2]), moves);
+    }
+
+    #[test]
+    fn dense_matches_hashed() {
+        let (rules, updates) = parse("input/2024/day5.txt").unwrap();
+        let dense = CustomOrd::new(rules.clone());
+        let hashed = CustomOrd::hashed(rules);
+        assert!(matches!(dense.rules, Rules::Dense { .. }));
+
+        for update in &updates {
+            assert_eq!(hashed.check(update).is_empty(), hashed.is_ordered(update));
+            assert_eq!(hashed.is_ordered(update), dense.is_ordered(update));
+            assert_eq!(hashed.check(update), dense.check(update));
+        }
+    }
+
+    #[test]
+    fn explain_contradicting_rules() {
+        let input = "0|1\n1|5\n5|1\n\n1,1,5\n1,0,5";
+        let (rules, updates) = parse_input(input).unwrap();
+        let ord = CustomOrd::new(rules);
+
+        let update = &updates[0];
+        assert!(!ord.is_ordered(update));
+        assert_eq!(
+            vec![
+                "1 (position 1) printed before 5 (position 3) breaks 5|1",
+                "1 (position 2) printed before 5 (position 3) breaks 5|1",
+            ],
+            ord.check(update)
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            "
+update 1 can't be fixed, ordering rules form a cycle: 5 -> 1 -> 5:
+  1 (position 1) printed before 5 (position 3) breaks 5|1
+  1 (position 2) printed before 5 (position 3) breaks 5|1
+update 2 can't be fixed, ordering rules form a cycle: 5 -> 1 -> 5:
+  1 (position 1) printed before 0 (position 2) breaks 0|1
+  1 (position 1) printed before 5 (position 3) breaks 5|1",
+            part2_explain(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn moves_with_incomplete_rules() {
+        let (rules, updates) = parse_input("1|2\n\n3,2,1\n2,4,1,3,5\n3,3,1").unwrap();
+        let ord = CustomOrd::new(rules);
+
+        let moves: Result<Vec<usize>, Error> =
+            updates.iter().map(|u| ord.moves_to_fix(u)).collect();
+        assert_eq!(Ok(vec![1, 1, 0]), moves);
+        assert_eq!(
+            "
+update 1 needs 1 moves:
+  2 (position 2) printed before 1 (position 3) breaks 1|2",
+            part2_explain("1|2\n\n3,2,1").unwrap()
+        );
+    }
+
+    #[test]
+    fn moves_match_brute_force() {
+        let mut rng = Rng::new(0x2024_0023);
+        for _ in 0..300 {
+            // rules from smaller to larger pages can't form a cycle
+            let rules: Vec<OrdRule> = (0..rng.below(8))
+                .map(|_| {
+                    let (a, b) = (rng.range(0..=5), rng.range(0..=5));
+                    OrdRule {
+                        page1: a.min(b),
+                        page2: a.max(b),
+                    }
+                })
+                .filter(|rule| rule.page1 < rule.page2)
+                .collect();
+            let update = Update {
+                pages: (0..rng.below(4) * 2 + 1)
+                    .map(|_| rng.range(0..=5))
+                    .collect(),
+            };
+            let ord = CustomOrd::new(rules.clone());
+
+            // every order of the pages the rules allow, and how many pages
+            // stay in place on the way there
+            let mut best = 0;
+            permutations(&update.pages, &mut vec![], &mut |order| {
+                let order = Update {
+                    pages: order.to_vec(),
+                };
+                if ord.check(&order).is_empty() {
+                    best = best.max(common_subsequence(&update.pages, &order.pages));
+                }
+            });
+            assert_eq!(
+                Ok(update.len() - best),
+                ord.moves_to_fix(&update),
+                "{rules:?} {update:?}"
+            );
+        }
+    }
+
+    fn permutations(rest: &[i32], prefix: &mut Vec<i32>, f: &mut impl FnMut(&[i32])) {
+        if rest.is_empty() {
+            f(prefix);
+        }
+        for idx in 0..rest.len() {
+            let mut others = rest.to_vec();
+            prefix.push(others.remove(idx));
+            permutations(&others, prefix, f);
+            prefix.pop();
+        }
+    }
+
+    fn common_subsequence(a: &[i32], b: &[i32]) -> usize {
+        let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                lengths[i + 1][j + 1] = if x == y {
+                    lengths[i][j] + 1
+                } else {
+                    lengths[i][j + 1].max(lengths[i + 1][j])
+                };
+            }
+        }
+        lengths[a.len()][b.len()]
+    }
+
+    #[test]
+    fn random_rules_agree() {
+        let mut rng = Rng::new(0x2024_0005);
+        for _ in 0..20_000 {
+            // few pages so duplicates, self rules and contradictions are
+            // common, the last two pages have no rules
+            let rules: Vec<OrdRule> = (0..rng.below(12))
+                .map(|_| OrdRule {
+                    page1: rng.range(0..=5),
+                    page2: rng.range(0..=5),
+                })
+                .collect();
+            let update = Update {
+                pages: (0..rng.below(4) * 2 + 1)
+                    .map(|_| rng.range(0..=7))
+                    .collect(),
+            };
+            let dense = CustomOrd::new(rules.clone());
+            let hashed = CustomOrd::hashed(rules.clone());
+
+            let expected = update.pages.iter().enumerate().all(|(idx, page)| {
+                update.pages[idx + 1..].iter().all(|later| {
+                    !rules.contains(&OrdRule {
+                        page1: *later,
+                        page2: *page,
+                    })
+                })
+            });
+            assert_eq!(expected, dense.is_ordered(&update), "{rules:?} {update:?}");
+            assert_eq!(expected, hashed.is_ordered(&update), "{rules:?} {update:?}");
+            assert_eq!(expected, dense.check(&update).is_empty());
+            assert_eq!(dense.check(&update), hashed.check(&update));
+        }
+    }
+
+    #[test]
+    fn large_pages_fall_back_to_hashed() {
+        let (rules, updates) = parse_input("5|2000\n1|5\n\n2000,5,1\n1,5,2000").unwrap();
+        let ord = CustomOrd::new(rules);
+        assert!(matches!(ord.rules, Rules::Hashed(_)));
+
+        assert!(!ord.is_ordered(&updates[0]));
+        assert!(ord.is_ordered(&updates[1]));
+    }
+
+    #[test]
+    fn sort_errors() {
+        let (rules, updates) = parse_input("1|2\n2|3\n3|1\n4|5\n6|5\n\n3,2,1\n5,6,4").unwrap();
+        let ord = CustomOrd::new(rules);
+
+        let errors: Vec<String> = updates
+            .into_iter()
+            .map(|mut update| update.sort_by(&ord).unwrap_err().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "ordering rules form a cycle: 1 -> 2 -> 3 -> 1",
+                "no rule orders pages 6 and 4"
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn validate_rules() {
+        let (rules, updates) =
+            parse_input("1|2\n2|3\n3|1\n1|2\n4|5\n5|4\n6|6\n7|8\n\n1,2,3\n1,2,7\n7,8,6").unwrap();
+        let validation = validate(&rules, &updates);
+
+        assert_eq!(
+            Validation {
+                duplicates: vec![(OrdRule { page1: 1, page2: 2 }, 2)],
+                contradictions: vec![(4, 5)],
+                components: vec![vec![1, 2, 3], vec![4, 5], vec![6]],
+                update_cycles: vec![Some(vec![2, 3, 1]), None, Some(vec![6])],
+            },
+            validation
+        );
+        assert!(!validation.is_valid());
+        assert_eq!(
+            "duplicate rules: 1|2 (x2)
+contradicting rules: 4|5 and 5|4
+cyclic components: 3 pages [1, 2, 3] 2 pages [4, 5] 1 pages [6]
+updates with cyclic rules: 2 of 3
+  update 1: ordering rules form a cycle: 2 -> 3 -> 1 -> 2
+  update 3: ordering rules form a cycle: 6 -> 6
+",
+            validation.to_string()
+        );
+    }
+
+    #[test]
+    fn validate_input() {
+        let (rules, updates) = parse("input/2024/day5.txt").unwrap();
+        let validation = validate(&rules, &updates);
+
+        // the rules order every pair of pages, globally they are one big cycle
+        assert!(validation.is_valid());
+        assert_eq!(1, validation.components.len());
+    }
+
+    #[test]
+    fn parse_input_error_position() {
+        let input = "47|53\n97|\n\n75,47";
+
+        assert_eq!(
+            Err(Error::parsing(2, 4, ParseErrorKind::IncompleteOrderRule)),
+            parse_input(input)
+        );
+    }
+
+    #[test]
+    fn parse_update_error_position() {
+        let e = parse_input("47|53\n\n75,,47").unwrap_err();
+
+        assert_eq!(
+            "line 3, column 4: invalid number \"\" (cannot parse integer from empty string)",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn parse_strict_rules() {
+        let errors: Vec<String> = [
+            "12x|34",
+            "1|2|3",
+            "12",
+            "|5",
+            "75,47",
+            " 1|2",
+            "1|99999999999",
+        ]
+        .iter()
+        .map(|line| OrdRule::parse(line).unwrap_err().to_string())
+        .collect();
+
+        assert_eq!(
+            vec![
+                "line 1, column 3: unexpected character 'x'",
+                "line 1, column 4: unexpected character '|'",
+                "line 1, column 3: incomplete ordering rule",
+                "line 1, column 1: incomplete ordering rule",
+                "line 1, column 3: unexpected character ','",
+                "line 1, column 1: unexpected character ' '",
+                "line 1, column 3: invalid number \"99999999999\" (number too large to fit in target type)",
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn parse_strict_sections() {
+        let errors: Vec<String> = [
+            "47|53\n75,47,53",
+            "47|53\n\n75,47,53\n47|53",
+            "47|53\n\n75,47,53\n\n53,47,75",
+            "47|53\n\n75,47,53\n53,47",
+            "47|53\n\n75;47;53",
+        ]
+        .iter()
+        .map(|input| parse_input(input).unwrap_err().to_string())
+        .collect();
+
+        assert_eq!(
+            vec![
+                "line 2, column 3: unexpected character ','",
+                "line 4, column 3: unexpected character '|'",
+                "line 4, column 1: update without pages",
+                "line 4, column 1: update of 2 pages has no middle page",
+                "line 3, column 3: unexpected character ';'",
+            ],
+            errors
+        );
+
+        // blank lines may trail the updates
+        assert_eq!(1, parse_input("47|53\n\n75,47,53\n\n\n").unwrap().1.len());
     }
 
     fn parse(path: &str) -> Result<(Vec<OrdRule>, Vec<Update>), Error> {
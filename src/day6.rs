@@ -1,20 +1,18 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Debug,
-    io::{self, stdout, Write},
-};
+use std::{collections::HashSet, fmt::Debug};
+
+use crate::error::{Error, ParseErrorKind};
 
 #[aoc(day6, part1)]
-pub fn part1(input: &str) -> usize {
-    let mut map = LabMap::new(input);
+pub fn part1(input: &str) -> Result<usize, Error> {
+    let mut map = LabMap::new(input)?;
     map.simulate();
-    map.path_length
+    Ok(map.path_length)
 }
 
 #[aoc(day6, part2)]
-pub fn part2(input: &str) -> usize {
+pub fn part2(input: &str) -> Result<usize, Error> {
     let mut count = 0;
-    let prototype = LabMap::new(input);
+    let prototype = LabMap::new(input)?;
     let free_guards_path = prototype.clone().simulate();
     let free_guards_path: HashSet<(usize, usize)> = free_guards_path
         .into_iter()
@@ -33,10 +31,7 @@ pub fn part2(input: &str) -> usize {
     for (i, j) in free_guards_path {
         let mut sim = prototype.clone();
 
-        if match prototype.content(i, j) {
-            '#' | '>' | 'v' | '<' | '^' => true,
-            _ => false,
-        } {
+        if matches!(prototype.content(i, j), '#' | '>' | 'v' | '<' | '^') {
             continue;
         }
         sim.set_content(i, j, 'O');
@@ -47,19 +42,9 @@ pub fn part2(input: &str) -> usize {
         }
     }
 
-    count
-}
-
-#[derive(Debug, PartialEq)]
-pub enum Error {
-    IO(std::io::ErrorKind),
+    Ok(count)
 }
 
-impl From<io::Error> for Error {
-    fn from(value: io::Error) -> Self {
-        Error::IO(value.kind())
-    }
-}
 #[derive(Clone)]
 pub struct LabMap {
     data: Vec<Vec<char>>,
@@ -69,11 +54,7 @@ pub struct LabMap {
 }
 
 impl LabMap {
-    fn len(&self) -> usize {
-        self.data.len()
-    }
-
-    pub fn new(lab_map_raw: &str) -> Self {
+    pub fn new(lab_map_raw: &str) -> Result<Self, Error> {
         let mut pos = None;
         let mut obstacles: Vec<(usize, usize)> = Vec::new();
         let data: Vec<Vec<char>> = lab_map_raw
@@ -95,13 +76,19 @@ impl LabMap {
                 row
             })
             .collect();
-        let pos: (isize, isize) = pos.map(|(row, col)| (row as isize, col as isize)).unwrap();
-        LabMap {
+        let pos: (isize, isize) =
+            pos.map(|(row, col)| (row as isize, col as isize))
+                .ok_or(Error::parsing(
+                    data.len().max(1),
+                    1,
+                    ParseErrorKind::MissingGuard,
+                ))?;
+        Ok(LabMap {
             data,
             path_length: 1,
             pos,
             new_content: Some('|'), //       obstacles,
-        }
+        })
     }
 
     pub fn is_obstacle(&self, row: usize, col: usize) -> bool {
@@ -178,7 +165,7 @@ impl LabMap {
                 break;
             }
         }
-        return path;
+        path
     }
 
     fn current_dir(&mut self) -> char {
@@ -291,9 +278,7 @@ impl Debug for LabMap {
 }
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
-
-    use io::Read;
+    use std::{fs::File, io::Read};
 
     use super::*;
 
@@ -306,7 +291,7 @@ mod tests {
     fn parse_test() {
         let lab_map_raw = load("test_data/day6.txt").unwrap();
 
-        let mut map = LabMap::new(lab_map_raw.as_str());
+        let map = LabMap::new(lab_map_raw.as_str()).unwrap();
 
         let expected = (6, 4);
         assert_eq!(expected, map.pos);
@@ -322,7 +307,7 @@ mod tests {
     fn simulate_test() {
         let lab_map_raw = load("test_data/day6.txt").unwrap();
 
-        let mut map = LabMap::new(lab_map_raw.as_str());
+        let mut map = LabMap::new(lab_map_raw.as_str()).unwrap();
 
         map.simulate();
 
@@ -333,14 +318,21 @@ mod tests {
     fn part2_test() {
         let lab_map_raw = load("test_data/day6.txt").unwrap();
 
-        assert_eq!(6, part2(lab_map_raw.as_str()));
+        assert_eq!(Ok(6), part2(lab_map_raw.as_str()));
     }
 
     #[test]
     fn part2_input_test() {
         let lab_map_raw = load("input/2024/day6.txt").unwrap();
 
-        assert_eq!(1915, part2(lab_map_raw.as_str()));
+        assert_eq!(Ok(1915), part2(lab_map_raw.as_str()));
+    }
+
+    #[test]
+    fn missing_guard_test() {
+        let e = LabMap::new("....\n.#..\n").unwrap_err();
+
+        assert_eq!("line 2, column 1: no guard on the map", e.to_string());
     }
 
     fn load(path: &str) -> Result<String, Error> {
@@ -0,0 +1,175 @@
+use std::{
+    fmt::{self, Display},
+    io::{self},
+    num::ParseIntError,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    InputError(io::Error),
+    /// Malformed puzzle input, `line` and `column` are 1-based.
+    ParsingInput {
+        line: usize,
+        column: usize,
+        kind: ParseErrorKind,
+    },
+    /// A result does not fit the numeric type it is computed in.
+    Overflow(String),
+    /// Ordering rules requiring each page to come before the next one, the
+    /// last page before the first.
+    RuleCycle(Vec<i32>),
+    /// No rule decides which of the two pages comes first.
+    AmbiguousOrder(i32, i32),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    Int {
+        token: String,
+        source: ParseIntError,
+    },
+    ColumnsExpected {
+        expected: usize,
+        found: usize,
+    },
+    IncompleteOrderRule,
+    MissingGuard,
+    UnexpectedChar(char),
+    EmptyUpdate,
+    /// An update with an even number of pages has no middle page.
+    EvenUpdate(usize),
+}
+
+impl Error {
+    pub fn parsing(line: usize, column: usize, kind: ParseErrorKind) -> Self {
+        Error::ParsingInput { line, column, kind }
+    }
+
+    /// Moves a parse error reported for a single line to `line` of the whole input.
+    pub fn on_line(self, line: usize) -> Self {
+        match self {
+            Error::ParsingInput { column, kind, .. } => Error::ParsingInput { line, column, kind },
+            e => e,
+        }
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::InputError(a), Error::InputError(b)) => a.kind() == b.kind(),
+            (
+                Error::ParsingInput { line, column, kind },
+                Error::ParsingInput {
+                    line: o_line,
+                    column: o_column,
+                    kind: o_kind,
+                },
+            ) => line == o_line && column == o_column && kind == o_kind,
+            (Error::Overflow(a), Error::Overflow(b)) => a == b,
+            (Error::RuleCycle(a), Error::RuleCycle(b)) => a == b,
+            (Error::AmbiguousOrder(a1, a2), Error::AmbiguousOrder(b1, b2)) => a1 == b1 && a2 == b2,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InputError(e) => write!(f, "reading input failed: {}", e),
+            Error::ParsingInput { line, column, kind } => {
+                write!(f, "line {}, column {}: {}", line, column, kind)
+            }
+            Error::Overflow(what) => write!(f, "{} overflowed", what),
+            Error::RuleCycle(pages) => {
+                write!(f, "ordering rules form a cycle: ")?;
+                for page in pages {
+                    write!(f, "{} -> ", page)?;
+                }
+                write!(f, "{}", pages.first().unwrap_or(&0))
+            }
+            Error::AmbiguousOrder(page1, page2) => {
+                write!(f, "no rule orders pages {} and {}", page1, page2)
+            }
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Int { token, source } => {
+                write!(f, "invalid number {:?} ({})", token, source)
+            }
+            ParseErrorKind::ColumnsExpected { expected, found } => {
+                write!(f, "expected {} columns, found {}", expected, found)
+            }
+            ParseErrorKind::IncompleteOrderRule => write!(f, "incomplete ordering rule"),
+            ParseErrorKind::MissingGuard => write!(f, "no guard on the map"),
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ParseErrorKind::EmptyUpdate => write!(f, "update without pages"),
+            ParseErrorKind::EvenUpdate(len) => {
+                write!(f, "update of {} pages has no middle page", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InputError(e) => Some(e),
+            Error::ParsingInput {
+                kind: ParseErrorKind::Int { source, .. },
+                ..
+            } => Some(source),
+            Error::ParsingInput { .. }
+            | Error::Overflow(_)
+            | Error::RuleCycle(_)
+            | Error::AmbiguousOrder(..) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::InputError(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_parsing_input() {
+        let e = Error::parsing(
+            1,
+            4,
+            ParseErrorKind::ColumnsExpected {
+                expected: 2,
+                found: 3,
+            },
+        )
+        .on_line(7);
+
+        assert_eq!(
+            "line 7, column 4: expected 2 columns, found 3",
+            e.to_string()
+        );
+    }
+
+    #[test]
+    fn display_parsing_int() {
+        let kind = ParseErrorKind::Int {
+            token: "x".to_string(),
+            source: "x".parse::<i32>().unwrap_err(),
+        };
+
+        assert_eq!(
+            "line 2, column 1: invalid number \"x\" (invalid digit found in string)",
+            Error::parsing(2, 1, kind).to_string()
+        );
+    }
+}
@@ -10,5 +10,9 @@ pub mod day3;
 pub mod day4;
 pub mod day6;
 pub mod day5;
+pub mod error;
+pub mod number;
+#[cfg(test)]
+mod test_rng;
 
 aoc_lib!{ year = 2024 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// Integer types the day1 list metrics and the day3 sums can be computed in.
+pub trait Number: Copy + Ord + Hash + Debug + Display {
+    const ZERO: Self;
+    const MAX: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn from_count(count: usize) -> Option<Self>;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                const ZERO: Self = 0;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+
+                fn saturating_add(self, rhs: Self) -> Self {
+                    <$t>::saturating_add(self, rhs)
+                }
+
+                fn saturating_mul(self, rhs: Self) -> Self {
+                    <$t>::saturating_mul(self, rhs)
+                }
+
+                fn from_count(count: usize) -> Option<Self> {
+                    <$t>::try_from(count).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(i32, i64, i128, u32, u64, u128);
+
+/// How computations deal with results that do not fit the number type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arithmetic {
+    /// Give up and return `None`.
+    Checked,
+    /// Clamp to the bounds of the type.
+    Saturating,
+}
+
+impl Arithmetic {
+    pub(crate) fn add<T: Number>(self, a: T, b: T) -> Option<T> {
+        match self {
+            Arithmetic::Checked => a.checked_add(b),
+            Arithmetic::Saturating => Some(a.saturating_add(b)),
+        }
+    }
+
+    pub(crate) fn mul<T: Number>(self, a: T, b: T) -> Option<T> {
+        match self {
+            Arithmetic::Checked => a.checked_mul(b),
+            Arithmetic::Saturating => Some(a.saturating_mul(b)),
+        }
+    }
+
+    /// Results that can only overflow upwards saturate at `T::MAX`.
+    fn upper_bound<T: Number>(self, result: Option<T>) -> Option<T> {
+        match self {
+            Arithmetic::Checked => result,
+            Arithmetic::Saturating => result.or(Some(T::MAX)),
+        }
+    }
+
+    pub(crate) fn abs_diff<T: Number>(self, a: T, b: T) -> Option<T> {
+        self.upper_bound(a.max(b).checked_sub(a.min(b)))
+    }
+
+    pub(crate) fn count<T: Number>(self, count: usize) -> Option<T> {
+        self.upper_bound(T::from_count(count))
+    }
+}
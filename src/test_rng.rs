@@ -0,0 +1,27 @@
+use std::ops::RangeInclusive;
+
+/// Small xorshift generator so the randomized tests are reproducible offline.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform enough in `0..n` for small `n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub(crate) fn range(&mut self, range: RangeInclusive<i32>) -> i32 {
+        let span = (range.end() - range.start() + 1) as u64;
+        range.start() + (self.next_u64() % span) as i32
+    }
+}
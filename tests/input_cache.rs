@@ -0,0 +1,92 @@
+#[allow(dead_code)]
+#[path = "../benches/common.rs"]
+mod common;
+
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    thread,
+};
+
+use common::{Error, HttpFetcher, InputProvider};
+
+fn temp_cache(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("aoc2024-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Serves `body` for exactly one request and returns the base url and the
+/// handle yielding the raw request that was received.
+fn serve_once(body: &'static str) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+    (url, handle)
+}
+
+#[test]
+fn cached_input_is_used_without_fetcher() {
+    let cache = temp_cache("cached");
+    fs::create_dir_all(cache.join("2024")).unwrap();
+    fs::write(cache.join("2024/day1.txt"), "3   4\n").unwrap();
+
+    let provider = InputProvider::new(&cache, None);
+
+    assert_eq!("3   4\n", provider.input(1).unwrap());
+    fs::remove_dir_all(cache).unwrap();
+}
+
+#[test]
+fn missing_input_without_fetcher_errors() {
+    let cache = temp_cache("missing");
+    let provider = InputProvider::new(&cache, None);
+
+    let e = provider.input(1).unwrap_err();
+    assert!(matches!(e, Error::NotCached(_)));
+    assert!(e
+        .to_string()
+        .ends_with("day1.txt not cached and no fetcher configured"));
+}
+
+#[test]
+fn fetched_input_is_written_to_cache() {
+    let cache = temp_cache("fetched");
+    let (url, server) = serve_once("1 2 3\n");
+    let provider = InputProvider::new(&cache, Some(Box::new(HttpFetcher::new(&url, "secret"))));
+
+    assert_eq!("1 2 3\n", provider.input(2).unwrap());
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("GET /2024/day/2/input "));
+    assert!(request.contains("session=secret"));
+    assert_eq!(
+        "1 2 3\n",
+        fs::read_to_string(cache.join("2024/day2.txt")).unwrap()
+    );
+
+    // second lookup is served from the cache, the stand-in server is gone
+    assert_eq!("1 2 3\n", provider.input(2).unwrap());
+    fs::remove_dir_all(cache).unwrap();
+}